@@ -3,8 +3,8 @@ mod system_adjacent_ai;
 mod system_approach_ai;
 mod system_chase_ai;
 mod system_default_move;
+mod system_energy;
 mod system_flee_ai;
-mod system_initiative;
 mod system_visible_ai;
 mod turn_status;
 
@@ -13,7 +13,7 @@ pub use system_adjacent_ai::*;
 pub use system_approach_ai::*;
 pub use system_chase_ai::*;
 pub use system_default_move::*;
+pub use system_energy::*;
 pub use system_flee_ai::*;
-pub use system_initiative::*;
 pub use system_visible_ai::*;
 pub use turn_status::*;
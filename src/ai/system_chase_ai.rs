@@ -1,8 +1,17 @@
 extern crate specs;
-use crate::{Chasing, EntityMoved, Map, MyTurn, Position, Viewshed};
+use crate::{ChaseSettings, Chasing, EntityMoved, Map, MyTurn, Position, Viewshed};
+use rltk::{DistanceAlg, Point};
 use specs::prelude::*;
-use std::collections::HashMap;
 
+/// Path length after which a mob stops chasing, when its raws don't say otherwise.
+pub const DEFAULT_CHASE_PATH: i32 = 15;
+/// Turns spent searching around the last known position of the target.
+pub const DEFAULT_SEARCH_TURNS: i32 = 5;
+/// How far from the last known position the search goes.
+const SEARCH_RADIUS: f32 = 3.0;
+
+/// Follows a target out of sight: goes to the tile where it was last seen,
+/// searches around for a few turns, then gives up and lets `DefaultMoveAI` play.
 pub struct ChaseAI {}
 
 impl<'a> System<'a> for ChaseAI {
@@ -10,10 +19,12 @@ impl<'a> System<'a> for ChaseAI {
     type SystemData = (
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, Chasing>,
+        ReadStorage<'a, ChaseSettings>,
         WriteStorage<'a, Position>,
         WriteExpect<'a, Map>,
         WriteStorage<'a, Viewshed>,
         WriteStorage<'a, EntityMoved>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
         Entities<'a>,
     );
 
@@ -21,54 +32,90 @@ impl<'a> System<'a> for ChaseAI {
         let (
             mut turns,
             mut chasing,
+            settings,
             mut positions,
             mut map,
             mut viewsheds,
             mut entity_moved,
+            mut rng,
             entities,
         ) = data;
 
-        let mut targets: HashMap<Entity, (i32, i32)> = HashMap::new();
         let mut end_chase: Vec<Entity> = Vec::new();
         for (entity, _turn, chasing) in (&entities, &turns, &chasing).join() {
-            let target_pos = positions.get(chasing.target);
-            if let Some(target_pos) = target_pos {
-                targets.insert(entity, (target_pos.x, target_pos.y));
-            } else {
+            if positions.get(chasing.target).is_none() {
                 end_chase.push(entity);
             }
         }
-
         for done in end_chase.iter() {
             chasing.remove(*done);
         }
         end_chase.clear();
 
         let mut turn_done: Vec<Entity> = Vec::new();
-        for (entity, mut pos, _chase, mut viewshed, _myturn) in
-            (&entities, &mut positions, &chasing, &mut viewsheds, &turns).join()
+        for (entity, pos, chase, viewshed, _myturn) in (
+            &entities,
+            &mut positions,
+            &mut chasing,
+            &mut viewsheds,
+            &turns,
+        )
+            .join()
         {
-            turn_done.push(entity);
-            let target_pos = targets[&entity];
-            let path = rltk::a_star_search(
-                map.xy_idx(pos.x, pos.y) as i32,
-                map.xy_idx(target_pos.0, target_pos.1) as i32,
-                &mut *map,
+            let (max_path, search_turns) = match settings.get(entity) {
+                Some(s) => (s.max_path, s.search_turns),
+                None => (DEFAULT_CHASE_PATH, DEFAULT_SEARCH_TURNS),
+            };
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let last_seen = Point::new(
+                chase.last_seen as i32 % map.width,
+                chase.last_seen as i32 / map.width,
             );
-            if path.success && path.steps.len() > 1 && path.steps.len() < 15 {
-                let mut idx = map.xy_idx(pos.x, pos.y);
-                map.blocked[idx] = false;
-                pos.x = path.steps[1] as i32 % map.width;
-                pos.y = path.steps[1] as i32 / map.width;
-                entity_moved
-                    .insert(entity, EntityMoved {})
-                    .expect("Unable to insert marker");
-                idx = map.xy_idx(pos.x, pos.y);
-                map.blocked[idx] = true;
-                viewshed.dirty = true;
-                turn_done.push(entity);
-            } else {
-                end_chase.push(entity);
+            let distance = DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), last_seen);
+
+            // Arrivé à la dernière position connue : on fouille autour
+            if chase.searching.is_none() && distance < 1.5 {
+                chase.searching = Some(search_turns);
+            }
+
+            let destination = match chase.searching {
+                Some(turns_left) if turns_left <= 0 => None,
+                Some(turns_left) => {
+                    chase.searching = Some(turns_left - 1);
+                    turn_done.push(entity);
+                    search_step(&map, &mut rng, pos, last_seen)
+                }
+                None => {
+                    let path = rltk::a_star_search(my_idx as i32, chase.last_seen as i32, &*map);
+                    if path.success && path.steps.len() > 1 && path.steps.len() < max_path as usize
+                    {
+                        turn_done.push(entity);
+                        Some(path.steps[1])
+                    } else {
+                        None
+                    }
+                }
+            };
+
+            match destination {
+                None => {
+                    // Plus de chemin ou fouille finie : abandon, le tour reste à DefaultMoveAI
+                    if !turn_done.contains(&entity) {
+                        end_chase.push(entity);
+                    }
+                }
+                Some(dest_idx) => {
+                    if !map.blocked[dest_idx] {
+                        map.blocked[my_idx] = false;
+                        pos.x = dest_idx as i32 % map.width;
+                        pos.y = dest_idx as i32 / map.width;
+                        entity_moved
+                            .insert(entity, EntityMoved {})
+                            .expect("Unable to insert marker");
+                        map.blocked[dest_idx] = true;
+                        viewshed.dirty = true;
+                    }
+                }
             }
         }
 
@@ -80,3 +127,30 @@ impl<'a> System<'a> for ChaseAI {
         }
     }
 }
+
+/// A random free tile next to `pos`, staying close to `last_seen`.
+fn search_step(
+    map: &Map,
+    rng: &mut rltk::RandomNumberGenerator,
+    pos: &Position,
+    last_seen: Point,
+) -> Option<usize> {
+    let (dx, dy) = match rng.roll_dice(1, 8) {
+        1 => (-1, -1),
+        2 => (0, -1),
+        3 => (1, -1),
+        4 => (-1, 0),
+        5 => (1, 0),
+        6 => (-1, 1),
+        7 => (0, 1),
+        _ => (1, 1),
+    };
+    let (x, y) = (pos.x + dx, pos.y + dy);
+    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 {
+        return None;
+    }
+    if DistanceAlg::Pythagoras.distance2d(Point::new(x, y), last_seen) > SEARCH_RADIUS {
+        return None;
+    }
+    Some(map.xy_idx(x, y))
+}
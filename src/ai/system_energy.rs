@@ -0,0 +1,138 @@
+extern crate specs;
+use crate::{
+    gamelog::GameLog, map::TileType, raws::Reaction, speed, ActionKind, Attributes, Energy,
+    EntityMoved, Faction, Map, MyTurn, Name, Position, RunState, StatusEffects, Viewshed,
+    ACTION_COST,
+};
+use specs::prelude::*;
+
+/// Hands out energy every tick, and the turn to whoever has enough.
+pub struct EnergySystem {}
+
+impl<'a> System<'a> for EnergySystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, Energy>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, MyTurn>,
+        Entities<'a>,
+        ReadStorage<'a, Attributes>,
+        WriteExpect<'a, RunState>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, rltk::Point>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Faction>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut energies,
+            positions,
+            mut turns,
+            entities,
+            attributes,
+            mut runstate,
+            player,
+            player_pos,
+            statuses,
+            viewsheds,
+            names,
+            mut log,
+            factions,
+        ) = data;
+
+        if *runstate != RunState::Ticking {
+            return;
+        }
+
+        // Clear any remaining MyTurn we left by mistake
+        turns.clear();
+
+        let player_speed = energies.get(*player).map_or(0, |e| {
+            speed(e, attributes.get(*player), statuses.get(*player))
+        });
+
+        for (entity, energy, pos) in (&entities, &mut energies, &positions).join() {
+            let my_speed = speed(energy, attributes.get(entity), statuses.get(entity));
+            energy.current += my_speed;
+            if energy.current < ACTION_COST {
+                continue;
+            }
+
+            // Le tour se paie d'avance, les actions moins chères rendent la différence
+            energy.current -= ACTION_COST;
+            let mut myturn = true;
+
+            // If its the player, we want to go to an AwaitingInput state
+            if entity == *player {
+                *runstate = RunState::AwaitingInput;
+            } else {
+                let distance = rltk::DistanceAlg::Pythagoras
+                    .distance2d(*player_pos, rltk::Point::new(pos.x, pos.y));
+                if distance > 20.0 {
+                    myturn = false;
+                }
+
+                let faster = my_speed > player_speed;
+                let seen = viewsheds
+                    .get(*player)
+                    .is_some_and(|vs| vs.visible_tiles.contains(&rltk::Point::new(pos.x, pos.y)));
+                if faster
+                    && !energy.faster_than_player
+                    && seen
+                    && hostile(entity, *player, &factions)
+                {
+                    if let Some(name) = names.get(entity) {
+                        log.entries
+                            .insert(0, format!("{} is faster than you!", name.name));
+                    }
+                }
+                energy.faster_than_player = faster;
+            }
+
+            //It's my turn
+            if myturn {
+                turns
+                    .insert(entity, MyTurn {})
+                    .expect("Unable to insert turn");
+            }
+        }
+    }
+}
+
+/// Only the ones that would attack the player are worth a warning.
+fn hostile(entity: Entity, player: Entity, factions: &ReadStorage<Faction>) -> bool {
+    let player_faction = factions.get(player).map_or("Player", |f| f.name.as_str());
+    factions.get(entity).is_some_and(|faction| {
+        crate::raws::faction_reaction(
+            &faction.name,
+            player_faction,
+            &crate::raws::RAWS.lock().unwrap(),
+        ) == Reaction::Attack
+    })
+}
+
+/// Moving through shallow water takes longer than a step on dry land.
+pub struct MovementCostSystem {}
+
+impl<'a> System<'a> for MovementCostSystem {
+    type SystemData = (
+        ReadStorage<'a, EntityMoved>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, Energy>,
+        ReadExpect<'a, Map>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (moved, positions, mut energies, map) = data;
+
+        for (_moved, pos, energy) in (&moved, &positions, &mut energies).join() {
+            if map.tiles[map.xy_idx(pos.x, pos.y)] == TileType::ShallowWater {
+                energy.spend(ActionKind::Wade);
+            }
+        }
+    }
+}
@@ -1,15 +1,22 @@
 extern crate specs;
 use crate::{
-    raws::Reaction, Chasing, Faction, Map, MyTurn, Position, Viewshed, WantsToApproach, WantsToFlee,
+    line_of_fire, ranged_attack, raws::Reaction, skill_bonus, spell_in_range, AreaOfEffect,
+    Chasing, Equipped, Faction, KnownSpells, Map, MeleeWeapon, MyTurn, Name, NaturalAttackDefense,
+    Pools, Position, Ranged, RangedWeapon, Skill, Skills, SpellTemplate, Viewshed, WantsToApproach,
+    WantsToCastSpell, WantsToFlee, WantsToShoot,
 };
+use rltk::{DistanceAlg, Point};
 use specs::prelude::*;
 
+/// Closer than this, a mob that can shoot steps back instead.
+const KEEP_DISTANCE: f32 = 3.0;
+
 pub struct VisibleAI {}
 
 impl<'a> System<'a> for VisibleAI {
     #[allow(clippy::type_complexity)]
     type SystemData = (
-        ReadStorage<'a, MyTurn>,
+        WriteStorage<'a, MyTurn>,
         ReadStorage<'a, Faction>,
         ReadStorage<'a, Position>,
         ReadExpect<'a, Map>,
@@ -19,11 +26,24 @@ impl<'a> System<'a> for VisibleAI {
         ReadExpect<'a, Entity>,
         ReadStorage<'a, Viewshed>,
         WriteStorage<'a, Chasing>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, NaturalAttackDefense>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, MeleeWeapon>,
+        ReadStorage<'a, RangedWeapon>,
+        ReadStorage<'a, KnownSpells>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, SpellTemplate>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Ranged>,
+        ReadStorage<'a, AreaOfEffect>,
+        WriteStorage<'a, WantsToCastSpell>,
+        ReadStorage<'a, Skills>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
-            turns,
+            mut turns,
             factions,
             positions,
             map,
@@ -33,8 +53,22 @@ impl<'a> System<'a> for VisibleAI {
             player,
             viewsheds,
             mut chasing,
+            mut want_shoot,
+            natural,
+            equipped,
+            melee_weapons,
+            ranged_weapons,
+            known_spells,
+            pools,
+            spell_templates,
+            names,
+            ranged,
+            aoe,
+            mut want_cast,
+            skills,
         ) = data;
 
+        let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, _turn, my_faction, pos, viewshed) in
             (&entities, &turns, &factions, &positions, &viewsheds).join()
         {
@@ -49,37 +83,121 @@ impl<'a> System<'a> for VisibleAI {
                     }
                 }
 
-                let mut done = false;
                 for reaction in reactions.iter() {
-                    match reaction.1 {
-                        Reaction::Attack => {
-                            want_approach
-                                .insert(
-                                    entity,
-                                    WantsToApproach {
-                                        idx: reaction.0 as i32,
-                                    },
-                                )
-                                .expect("Unable to insert");
-                            chasing
-                                .insert(entity, Chasing { target: reaction.2 })
+                    if reaction.1 == Reaction::Flee {
+                        flee.push(reaction.0);
+                    }
+                }
+
+                // Une seule cible par tour : l'ennemi le plus proche
+                let me = Point::new(pos.x, pos.y);
+                let to_point =
+                    |idx: usize| Point::new(idx as i32 % map.width, idx as i32 / map.width);
+                let nearest = reactions
+                    .iter()
+                    .filter(|reaction| reaction.1 == Reaction::Attack)
+                    .map(|reaction| {
+                        let distance = DistanceAlg::Pythagoras.distance2d(me, to_point(reaction.0));
+                        (reaction, distance)
+                    })
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+                let (reaction, distance) = match nearest {
+                    None => {
+                        if !flee.is_empty() {
+                            want_flee
+                                .insert(entity, WantsToFlee { indices: flee })
                                 .expect("Unable to insert");
-                            done = true;
-                        }
-                        Reaction::Flee => {
-                            flee.push(reaction.0);
                         }
-                        _ => {}
+                        continue;
                     }
-                }
+                    Some(nearest) => nearest,
+                };
+                let target = to_point(reaction.0);
+                chasing
+                    .insert(
+                        entity,
+                        Chasing {
+                            target: reaction.2,
+                            last_seen: reaction.0,
+                            searching: None,
+                        },
+                    )
+                    .expect("Unable to insert");
 
-                if !done && !flee.is_empty() {
-                    want_flee
-                        .insert(entity, WantsToFlee { indices: flee })
-                        .expect("Unable to insert");
+                let magic = skills
+                    .get(entity)
+                    .map_or(0, |s| skill_bonus(Skill::Magic, s));
+                let spell = match (known_spells.get(entity), pools.get(entity)) {
+                    (Some(known), Some(stats)) => spell_in_range(
+                        known,
+                        stats.mana.current,
+                        magic,
+                        distance,
+                        &entities,
+                        &spell_templates,
+                        &names,
+                        &ranged,
+                        &aoe,
+                    )
+                    .filter(|_| line_of_fire(&map, me, target)),
+                    _ => None,
+                };
+                match (
+                    spell,
+                    ranged_attack(
+                        entity,
+                        None,
+                        &natural,
+                        &equipped,
+                        &melee_weapons,
+                        &ranged_weapons,
+                    ),
+                ) {
+                    (Some(spell), _) => {
+                        want_cast
+                            .insert(
+                                entity,
+                                WantsToCastSpell {
+                                    spell,
+                                    target: Some(target),
+                                },
+                            )
+                            .expect("Unable to insert");
+                        turn_done.push(entity);
+                    }
+                    // Trop près pour tirer : on recule
+                    (None, Some(_)) if distance < KEEP_DISTANCE => {
+                        flee.push(reaction.0);
+                        want_flee
+                            .insert(entity, WantsToFlee { indices: flee })
+                            .expect("Unable to insert");
+                    }
+                    (None, Some(attack))
+                        if distance <= attack.range as f32 && line_of_fire(&map, me, target) =>
+                    {
+                        want_shoot
+                            .insert(entity, WantsToShoot { target: reaction.2 })
+                            .expect("Unable to insert");
+                        turn_done.push(entity);
+                    }
+                    _ => {
+                        want_approach
+                            .insert(
+                                entity,
+                                WantsToApproach {
+                                    idx: reaction.0 as i32,
+                                },
+                            )
+                            .expect("Unable to insert");
+                    }
                 }
             }
         }
+
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
     }
 }
 
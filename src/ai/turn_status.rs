@@ -1,7 +1,12 @@
 extern crate specs;
-use crate::{Confusion, MyTurn, RunState};
+use crate::{
+    gamelog::GameLog, Confusion, DamageType, EffectQueue, EffectType, MyTurn, Name, RunState,
+    StatusChanged, StatusEffects, StatusKind, Targets, Viewshed,
+};
 use specs::prelude::*;
 
+/// Counts down the timed statuses of whoever has its turn, applies what they do
+/// each turn, and takes the turn away from the confused and the paralyzed.
 pub struct TurnStatusSystem {}
 
 impl<'a> System<'a> for TurnStatusSystem {
@@ -10,19 +15,40 @@ impl<'a> System<'a> for TurnStatusSystem {
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, Confusion>,
         Entities<'a>,
-        ReadExpect<'a, RunState>,
+        WriteExpect<'a, RunState>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, StatusChanged>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, EffectQueue>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Entity>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut confusion, entities, runstate) = data;
+        let (
+            mut turns,
+            mut confusion,
+            entities,
+            mut runstate,
+            mut statuses,
+            mut status_changed,
+            mut viewsheds,
+            mut effects,
+            mut log,
+            names,
+            player,
+        ) = data;
 
-        if *runstate != RunState::Ticking {
+        // L'énergie vient de donner le tour au joueur : les autres jouent quand même ce tour-ci
+        if *runstate != RunState::Ticking && *runstate != RunState::AwaitingInput {
             return;
         }
 
         let mut not_my_turn: Vec<Entity> = Vec::new();
         let mut not_confused: Vec<Entity> = Vec::new();
-        for (entity, _turn, confused) in (&entities, &mut turns, &mut confusion).join() {
+        let mut paralyzed: Vec<Entity> = Vec::new();
+        for (entity, _turn, confused) in (&entities, &turns, &mut confusion).join() {
             confused.turns -= 1;
             if confused.turns < 1 {
                 not_confused.push(entity);
@@ -31,12 +57,90 @@ impl<'a> System<'a> for TurnStatusSystem {
             }
         }
 
+        for (entity, _turn, status_effects) in (&entities, &turns, &mut statuses).join() {
+            for status in status_effects.statuses.iter_mut() {
+                let tick = match status.kind {
+                    StatusKind::Poison => Some(EffectType::Damage {
+                        amount: status.potency,
+                        damage_type: Some(DamageType::Poison),
+                    }),
+                    StatusKind::Burning => Some(EffectType::Damage {
+                        amount: status.potency,
+                        damage_type: Some(DamageType::Fire),
+                    }),
+                    StatusKind::Bleeding => Some(EffectType::Damage {
+                        amount: status.potency,
+                        damage_type: None,
+                    }),
+                    StatusKind::Regeneration => Some(EffectType::Healing {
+                        amount: status.potency,
+                    }),
+                    StatusKind::Paralysis => {
+                        paralyzed.push(entity);
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(tick) = tick {
+                    effects.add_effect(None, tick, Targets::Single { target: entity });
+                }
+                status.turns -= 1;
+            }
+
+            let expired: Vec<StatusKind> = status_effects
+                .statuses
+                .iter()
+                .filter(|s| s.turns < 1)
+                .map(|s| s.kind)
+                .collect();
+            for kind in expired {
+                status_effects.remove(kind);
+                status_changed
+                    .insert(entity, StatusChanged {})
+                    .expect("Unable to insert status changed");
+                if kind == StatusKind::Blind {
+                    if let Some(viewshed) = viewsheds.get_mut(entity) {
+                        viewshed.dirty = true;
+                    }
+                }
+                if entity == *player {
+                    log.entries.insert(
+                        0,
+                        format!("You are no longer {}.", kind.name().to_lowercase()),
+                    );
+                } else if let Some(name) = names.get(entity) {
+                    log.entries.insert(
+                        0,
+                        format!("{} is no longer {}.", name.name, kind.name().to_lowercase()),
+                    );
+                }
+            }
+        }
+
         for e in not_my_turn {
             turns.remove(e);
         }
 
+        for e in paralyzed {
+            turns.remove(e);
+            // Le joueur paralysé passe son tour
+            if e == *player && *runstate == RunState::AwaitingInput {
+                *runstate = RunState::Ticking;
+            }
+        }
+
         for e in not_confused {
             confusion.remove(e);
         }
+
+        // Plus rien ne dure : inutile de garder le composant
+        let cured: Vec<Entity> = (&entities, &statuses)
+            .join()
+            .filter(|(_, s)| s.statuses.is_empty())
+            .map(|(e, _)| e)
+            .collect();
+        for e in cured {
+            statuses.remove(e);
+        }
     }
 }
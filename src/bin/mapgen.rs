@@ -0,0 +1,157 @@
+//! Génère un niveau sans fenêtre et l'écrit en ASCII et en JSON.
+//!
+//! `mapgen [--branch NAME] [--depth N] [--seed N] [--chain NAME] [--width W] [--height H] [--out DIR] [--history]`
+//!
+//! The level is built by `build_level`, exactly like the game does for this seed, branch
+//! (`Main` by default) and depth, the fallback map included. `--chain` only replaces the chain
+//! of the level. `--history` also writes every snapshot of the build as a numbered frame.
+
+use roguelike_rtlk::map_builders::{build_level, BuilderChain};
+use roguelike_rtlk::{raws, LevelKey, Map, MasterDungeonMap, Rect, TileType, MAIN_BRANCH};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct StartingPosition {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize)]
+struct MapDump<'a> {
+    name: &'a str,
+    branch: &'a str,
+    depth: i32,
+    seed: u64,
+    width: i32,
+    height: i32,
+    tiles: &'a [TileType],
+    starting_position: Option<StartingPosition>,
+    rooms: &'a Option<Vec<Rect>>,
+    corridors: &'a Option<Vec<Vec<usize>>>,
+    spawn_list: &'a [(usize, String)],
+}
+
+/// La valeur qui suit `flag` sur la ligne de commande, ex. `--depth 4`.
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    let position = args.iter().position(|a| a == flag)?;
+    Some(
+        args.get(position + 1)
+            .unwrap_or_else(|| panic!("{} expects a value", flag)),
+    )
+}
+
+fn arg_number<T: std::str::FromStr>(args: &[String], flag: &str, default: T) -> T {
+    match arg_value(args, flag) {
+        None => default,
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} expects a number", flag)),
+    }
+}
+
+fn tile_char(tile: TileType) -> char {
+    match tile {
+        TileType::Wall => '#',
+        TileType::Stalactite => '╨',
+        TileType::Stalagmite => '╥',
+        TileType::Floor => '.',
+        TileType::WoodFloor => '_',
+        TileType::Bridge => '=',
+        TileType::Gravel => ';',
+        TileType::DownStairs => '>',
+        TileType::UpStairs => '<',
+        TileType::Road => '≡',
+        TileType::Grass => '"',
+        TileType::ShallowWater => '~',
+        TileType::DeepWater => '≈',
+    }
+}
+
+fn map_to_ascii(map: &Map) -> String {
+    let mut ascii = String::new();
+    for row in map.tiles.chunks(map.width as usize) {
+        ascii.extend(row.iter().map(|t| tile_char(*t)));
+        ascii.push('\n');
+    }
+    ascii
+}
+
+fn write_file(path: &Path, contents: &str) {
+    fs::write(path, contents)
+        .unwrap_or_else(|e| panic!("Unable to write {} : {}", path.display(), e));
+}
+
+fn write_outputs(builder: &BuilderChain, seed: u64, out: &Path) {
+    let build_data = &builder.build_data;
+    let ascii = map_to_ascii(&build_data.map);
+    print!("{}", ascii);
+    write_file(&out.join("map.txt"), &ascii);
+
+    let dump = MapDump {
+        name: &build_data.map.name,
+        branch: &build_data.map.branch,
+        depth: build_data.map.depth,
+        seed,
+        width: build_data.map.width,
+        height: build_data.map.height,
+        tiles: &build_data.map.tiles,
+        starting_position: build_data
+            .starting_position
+            .as_ref()
+            .map(|pos| StartingPosition { x: pos.x, y: pos.y }),
+        rooms: &build_data.rooms,
+        corridors: &build_data.corridors,
+        spawn_list: &build_data.spawn_list,
+    };
+    let json = serde_json::to_string(&dump).expect("Unable to serialize the map");
+    write_file(&out.join("map.json"), &json);
+
+    for (i, snapshot) in build_data.history.iter().enumerate() {
+        write_file(
+            &out.join(format!("frame_{:03}.txt", i)),
+            &map_to_ascii(snapshot),
+        );
+    }
+    println!(
+        "{} ({} snapshots) written to {}",
+        build_data.map.name,
+        build_data.history.len(),
+        out.display()
+    );
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let branch = arg_value(&args, "--branch").map_or(MAIN_BRANCH, |b| b.as_str());
+    let depth: i32 = arg_number(&args, "--depth", 1);
+    let width: i32 = arg_number(&args, "--width", 80);
+    let height: i32 = arg_number(&args, "--height", 50);
+    let seed: u64 = match arg_value(&args, "--seed") {
+        Some(_) => arg_number(&args, "--seed", 0),
+        None => rltk::RandomNumberGenerator::new().next_u64(),
+    };
+    let out = Path::new(arg_value(&args, "--out").map_or("mapgen", |o| o.as_str()));
+    fs::create_dir_all(out).expect("Unable to create the output directory");
+
+    raws::load_raws();
+    println!("Seed : {}", seed);
+
+    // Même graine que le jeu pour ce niveau
+    let level = LevelKey::new(branch, depth);
+    let mut rng =
+        rltk::RandomNumberGenerator::seeded(MasterDungeonMap::new(seed).level_seed(&level));
+    let chain_name = arg_value(&args, "--chain");
+    let record_history = args.iter().any(|a| a == "--history");
+    let builder = build_level(
+        &level,
+        chain_name.map(|name| name.as_str()),
+        &mut rng,
+        width,
+        height,
+        record_history,
+    );
+
+    write_outputs(&builder, seed, out);
+}
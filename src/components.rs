@@ -11,12 +11,15 @@ use std::collections::HashMap;
 #[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum EquipmentSlot {
     Melee,
+    Ranged,
     Shield,
     Head,
     Torso,
     Legs,
     Feet,
     Hands,
+    Neck,
+    Ring,
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, PartialEq)]
@@ -27,13 +30,66 @@ pub enum HungerState {
     Starving,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Skill {
     Melee,
     Defense,
     Magic,
 }
 
+/// The timed statuses handled by `TurnStatusSystem`, besides `Confusion`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize)]
+pub enum StatusKind {
+    Poison,
+    Burning,
+    Bleeding,
+    Slow,
+    Haste,
+    Blind,
+    Regeneration,
+    Paralysis,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum DamageType {
+    Slashing,
+    Piercing,
+    #[default]
+    Bludgeoning,
+    Fire,
+    Cold,
+    Poison,
+    Magic,
+}
+
+/// What a landed attack can do besides its damage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OnHitEffect {
+    Status {
+        kind: StatusKind,
+        turns: i32,
+        potency: i32,
+    },
+    Confusion {
+        turns: i32,
+    },
+    Knockback {
+        distance: i32,
+    },
+    Disarm,
+    /// Heals the attacker of this percentage of the damage dealt.
+    LifeDrain {
+        percent: i32,
+    },
+}
+
+/// An on-hit effect, with its chance (in percent) to happen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnHit {
+    pub chance: i32,
+    pub effect: OnHitEffect,
+}
+
 #[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum WeaponAttribute {
     Might,
@@ -66,7 +122,9 @@ pub struct Quips {
 pub struct Targetable {}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Item {}
+pub struct Item {
+    pub weight_lbs: f32,
+}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Consumable {}
@@ -91,30 +149,6 @@ pub struct Name {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
-/// tuple (i32, bool) : damage done, and true if from player, false otherwise.
-#[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct SufferDamage {
-    pub amount: Vec<(i32, bool)>,
-}
-
-impl SufferDamage {
-    pub fn new_damage(
-        store: &mut WriteStorage<SufferDamage>,
-        victim: Entity,
-        amount: i32,
-        from_player: bool,
-    ) {
-        if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push((amount, from_player));
-        } else {
-            let dmg = SufferDamage {
-                amount: vec![(amount, from_player)],
-            };
-            store.insert(victim, dmg).expect("Unable to insert damage");
-        }
-    }
-}
-
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Ranged {
     pub range: i32,
@@ -123,6 +157,7 @@ pub struct Ranged {
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InflictsDamage {
     pub damage: i32,
+    pub damage_type: DamageType,
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
@@ -135,6 +170,53 @@ pub struct Confusion {
     pub turns: i32,
 }
 
+/// A status on a creature : `potency` is what it deals (or heals) each turn.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub turns: i32,
+    pub potency: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StatusEffects {
+    pub statuses: Vec<Status>,
+}
+
+/// Damage types halved, doubled or ignored. On creatures, and on what they wear.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Resistances {
+    pub resist: Vec<DamageType>,
+    pub vulnerable: Vec<DamageType>,
+    pub immune: Vec<DamageType>,
+}
+
+/// Items and traps that put statuses on their victims.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct InflictsStatus {
+    pub statuses: Vec<Status>,
+}
+
+/// A spell from the raws, spawned once per game. Casting it applies its effect
+/// components like a consumable, without consuming it.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SpellTemplate {
+    pub mana_cost: i32,
+    pub magic_skill: i32,
+}
+
+/// The spells one can cast, by name, in the order they were learned.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct KnownSpells {
+    pub spells: Vec<String>,
+}
+
+/// Spellbooks: reading one learns the spell, if the Magic skill is high enough.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct TeachesSpell {
+    pub spell: String,
+}
+
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Equippable {
     pub slot: EquipmentSlot,
@@ -147,6 +229,30 @@ pub struct MeleeWeapon {
     pub damage_die_type: i32,
     pub damage_bonus: i32,
     pub hit_bonus: i32,
+    pub damage_type: DamageType,
+    pub on_hit: Vec<OnHit>,
+}
+
+/// A weapon that can shoot, up to `range` tiles (its damage is in `MeleeWeapon`).
+/// With an `ammo` type, each shot uses one `Ammunition` of this type.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct RangedWeapon {
+    pub range: i32,
+    pub ammo: Option<String>,
+}
+
+/// Shot by the ranged weapons of `ammo_type`. A shot has `recover_chance` %
+/// to fall on the floor, where it can be picked up again.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Ammunition {
+    pub ammo_type: String,
+    pub recover_chance: i32,
+}
+
+/// How many items a stack holds: consumables and ammunition stack by name.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Quantity {
+    pub amount: i32,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -172,6 +278,14 @@ pub struct ProvidesFood {}
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct MagicMapper {}
 
+/// Equipment that can't be removed once worn, until the curse is lifted.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Cursed {}
+
+/// Lifts the curses of the equipment of its target: a scroll, or the temple priest.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct RemovesCurse {}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Hidden {}
 
@@ -210,6 +324,16 @@ pub struct Attributes {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Skills {
     pub skills: HashMap<Skill, i32>,
+    /// From equipment and statuses, see `AttributeModifierSystem`.
+    #[serde(default)]
+    pub modifiers: HashMap<Skill, i32>,
+}
+
+/// Attribute and skill points the player has yet to spend since their last level-up.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LevelUpPoints {
+    pub attribute_points: i32,
+    pub skill_points: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -233,6 +357,13 @@ pub struct NaturalAttack {
     pub damage_die_type: i32,
     pub damage_bonus: i32,
     pub hit_bonus: i32,
+    /// Shot from afar (spit, thrown rocks...), never used in melee.
+    #[serde(default)]
+    pub range: Option<i32>,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    #[serde(default)]
+    pub on_hit: Vec<OnHit>,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -251,6 +382,7 @@ pub struct OtherLevelPosition {
     pub x: i32,
     pub y: i32,
     pub depth: i32,
+    pub branch: String,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -259,9 +391,47 @@ pub struct LightSource {
     pub range: i32,
 }
 
+/// Every tick an entity gains its speed in energy, and it acts once it has
+/// `ACTION_COST`. What it does then is paid from it.
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Initiative {
+pub struct Energy {
     pub current: i32,
+    pub base_speed: i32,
+    /// Speed lost to heavy armor and to carrying too much, see `EncumbranceSystem`.
+    pub burden: i32,
+    /// Whether it was faster than the player the last time it acted.
+    pub faster_than_player: bool,
+}
+
+/// What an entity carries or wears has changed : its burden and its attribute
+/// modifiers must be recomputed.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct EquipmentChanged {}
+
+/// A status was inflicted or lifted : the attribute modifiers must be recomputed.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct StatusChanged {}
+
+/// Added to the attributes of whoever has the item equipped.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AttributeModifiers {
+    pub might: i32,
+    pub fitness: i32,
+    pub quickness: i32,
+    pub intelligence: i32,
+}
+
+/// Added to the skills of whoever has the item equipped.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SkillModifiers {
+    pub skills: HashMap<Skill, i32>,
+}
+
+/// A magic item: the rarity tier it rolled, and the color of its name.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Rarity {
+    pub tier: String,
+    pub color: RGB,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -293,6 +463,17 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToShoot {
+    pub target: Entity,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToCastSpell {
+    pub spell: Entity,
+    pub target: Option<rltk::Point>,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct WantsToPickupItem {
     pub collected_by: Entity,
@@ -333,9 +514,20 @@ pub struct MoveMode {
     pub mode: Movement,
 }
 
+/// `last_seen` is the tile where the target was last seen, `searching` the turns
+/// left to search around it once it is reached.
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Chasing {
     pub target: Entity,
+    pub last_seen: usize,
+    pub searching: Option<i32>,
+}
+
+/// How far a mob follows a target it has lost sight of (`chase` in the raws).
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ChaseSettings {
+    pub max_path: i32,
+    pub search_turns: i32,
 }
 
 // Serialization helper code. We need to implement ConvertSaveLoad for each type that contains an
@@ -352,4 +544,8 @@ pub struct SerializationHelper {
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct DMSerializationHelper {
     pub map: super::map::MasterDungeonMap,
+    #[serde(default)]
+    pub known_items: super::KnownItems,
+    #[serde(default)]
+    pub replay: Option<super::replay::Replay>,
 }
@@ -0,0 +1,77 @@
+use super::*;
+
+/// Seul endroit où les systèmes du jeu sont déclarés.
+/// Ordre : indexation → visibilité → énergie → IA → combat → sorts → objets → effets.
+/// Chaque système n'attend que ceux dont il lit le résultat. Ceux qui écrivent
+/// le même storage (journal, générateur aléatoire, file d'effets...) sont mis
+/// l'un après l'autre par specs ; les autres tournent en parallèle.
+pub struct GameDispatcher {
+    turn: Dispatcher<'static, 'static>,
+    effects: Dispatcher<'static, 'static>,
+}
+
+impl GameDispatcher {
+    pub fn new() -> GameDispatcher {
+        let turn = DispatcherBuilder::new()
+            .with(MapIndexingSystem {}, "map_indexing", &[])
+            .with(VisibilitySystem {}, "visibility", &["map_indexing"])
+            .with(ai::EnergySystem {}, "energy", &["visibility"])
+            .with(ai::TurnStatusSystem {}, "turn_status", &["energy"])
+            .with(ManaRegenSystem {}, "mana_regen", &["turn_status"])
+            .with(ai::QuipSystem {}, "quipping", &["turn_status"])
+            // IA : elle retire MyTurn à qui agit, on le lit donc avant
+            .with(
+                ai::AdjacentAI {},
+                "adjacent_ai",
+                &["mana_regen", "quipping"],
+            )
+            .with(ai::VisibleAI {}, "visible_ai", &["adjacent_ai"])
+            .with(ai::ApproachAI {}, "approach_ai", &["visible_ai"])
+            .with(ai::FleeAI {}, "flee_ai", &["visible_ai"])
+            .with(ai::ChaseAI {}, "chase_ai", &["approach_ai", "flee_ai"])
+            .with(ai::DefaultMoveAI {}, "default_move_ai", &["chase_ai"])
+            .with(
+                ai::MovementCostSystem {},
+                "movement_cost",
+                &["default_move_ai"],
+            )
+            .with(TriggerSystem {}, "triggers", &["movement_cost"])
+            // Combat
+            .with(MeleeCombatSystem {}, "melee_combat", &["adjacent_ai"])
+            .with(RangedCombatSystem {}, "ranged_combat", &["visible_ai"])
+            .with(SpellUseSystem {}, "spell_use", &["visible_ai"])
+            // Objets
+            .with(ItemCollectionSystem {}, "item_collection", &["turn_status"])
+            .with(ItemUseSystem {}, "item_use", &["item_collection"])
+            .with(ItemDropSystem {}, "item_drop", &["item_use"])
+            .with(ItemRemoveSystem {}, "item_remove", &["item_use"])
+            .with(HungerSystem {}, "hunger", &["turn_status"])
+            .with(
+                AttributeModifierSystem {},
+                "attribute_modifiers",
+                &["item_drop", "item_remove"],
+            )
+            .with(
+                EncumbranceSystem {},
+                "encumbrance",
+                &["attribute_modifiers"],
+            )
+            .build();
+
+        // Après la résolution de la file d'effets, pour afficher ce qu'ils ont produit
+        let effects = DispatcherBuilder::new()
+            .with(ParticleSpawnSystem {}, "particle_spawn", &[])
+            .with(LightingSystem {}, "lighting", &[])
+            .build();
+
+        GameDispatcher { turn, effects }
+    }
+
+    /// One pass of every system, with the effects queue resolved in between.
+    pub fn run(&mut self, ecs: &mut World) {
+        self.turn.dispatch(ecs);
+        effects::run_effects_queue(ecs);
+        self.effects.dispatch(ecs);
+        ecs.maintain();
+    }
+}
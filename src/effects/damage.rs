@@ -0,0 +1,205 @@
+use super::*;
+use crate::gamesystem::{
+    recompute_player_pools, ATTRIBUTE_POINTS_PER_LEVEL, SKILL_POINTS_PER_LEVEL,
+};
+
+pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
+    if let EffectType::Damage {
+        amount,
+        damage_type,
+    } = damage.effect_type
+    {
+        let amount = match damage_type {
+            None => amount,
+            Some(damage_type) => resist(ecs, amount, damage_type, target),
+        };
+        let mut pools = ecs.write_storage::<Pools>();
+        if let Some(pool) = pools.get_mut(target) {
+            let was_alive = pool.hit_points.current > 0;
+            pool.hit_points.current -= amount;
+            add_effect(
+                ecs,
+                None,
+                EffectType::Bloodstain,
+                Targets::Single { target },
+            );
+            // On ne meurt qu'une fois, même si plusieurs coups arrivent dans le même tour
+            if was_alive && pool.hit_points.current < 1 {
+                add_effect(
+                    ecs,
+                    damage.creator,
+                    EffectType::EntityDeath,
+                    Targets::Single { target },
+                );
+            }
+        }
+    }
+}
+
+/// Applies the resistances of the target and of what it wears, and says so in the log.
+fn resist(ecs: &World, amount: i32, damage_type: DamageType, target: Entity) -> i32 {
+    let resistances = ecs.read_storage::<Resistances>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let mut sources: Vec<&Resistances> = resistances.get(target).into_iter().collect();
+    for (wielded, item_resistances) in (&equipped, &resistances).join() {
+        if wielded.owner == target {
+            sources.push(item_resistances);
+        }
+    }
+    let resisted = resisted_damage(amount, damage_type, &sources);
+    if resisted == amount || amount == 0 {
+        return resisted;
+    }
+
+    let is_player = target == *ecs.fetch::<Entity>();
+    let name = match ecs.read_storage::<Name>().get(target) {
+        Some(name) => name.name.clone(),
+        None => return resisted,
+    };
+    let message = match (resisted, is_player) {
+        (0, true) => format!("You are immune to {}.", damage_type.name()),
+        (0, false) => format!("{} is immune to {}.", name, damage_type.name()),
+        (r, true) if r < amount => format!("You resist {}.", damage_type.name()),
+        (r, false) if r < amount => format!("{} resists {}.", name, damage_type.name()),
+        (_, true) => format!("You are vulnerable to {}!", damage_type.name()),
+        (_, false) => format!("{} is vulnerable to {}!", name, damage_type.name()),
+    };
+    ecs.fetch_mut::<GameLog>().entries.insert(0, message);
+    resisted
+}
+
+pub fn bloodstain(ecs: &mut World, tile_idx: i32) {
+    let mut map = ecs.fetch_mut::<Map>();
+    map.bloodstains.insert(tile_idx as usize);
+}
+
+/// Gives the experience of a kill to the player, and handles leveling up.
+/// The entity itself is removed later by `delete_the_dead`.
+pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    let player_entity = *ecs.fetch::<Entity>();
+    if effect.creator != Some(player_entity) {
+        return;
+    }
+
+    let mut pools = ecs.write_storage::<Pools>();
+    let xp_gain = match pools.get(target) {
+        None => return,
+        Some(stats) => stats.level * 100,
+    };
+
+    let attributes = ecs.read_storage::<Attributes>();
+    let player_stats = pools.get_mut(player_entity).unwrap();
+    let player_attributes = attributes.get(player_entity).unwrap();
+    player_stats.xp += xp_gain;
+    if player_stats.xp >= player_stats.level * 1000 {
+        // We've gone up a level!
+        player_stats.level += 1;
+        ecs.fetch_mut::<GameLog>().entries.insert(
+            0,
+            format!("Congratulations, you are now level {}", player_stats.level),
+        );
+        recompute_player_pools(player_stats, player_attributes);
+        player_stats.hit_points.current = player_stats.hit_points.max;
+        player_stats.mana.current = player_stats.mana.max;
+
+        // Les points se dépensent dans le menu de montée de niveau
+        let mut level_up_points = ecs.write_storage::<LevelUpPoints>();
+        let points = level_up_points
+            .entry(player_entity)
+            .expect("Unable to insert level up points")
+            .or_insert_with(LevelUpPoints::default);
+        points.attribute_points += ATTRIBUTE_POINTS_PER_LEVEL;
+        points.skill_points += SKILL_POINTS_PER_LEVEL;
+
+        let player_pos = ecs.fetch::<rltk::Point>();
+        let mut particles = ecs.fetch_mut::<ParticleBuilder>();
+        for i in 0..10 {
+            if player_pos.y - i > 1 {
+                particles.request(
+                    player_pos.x,
+                    player_pos.y - i,
+                    rltk::to_cp437('░'),
+                    rltk::RGB::named(rltk::GOLD),
+                    rltk::RGB::named(rltk::BLACK),
+                    400.0,
+                );
+            }
+        }
+    }
+}
+
+pub fn heal_damage(ecs: &mut World, heal: &EffectSpawner, target: Entity) {
+    let mut pools = ecs.write_storage::<Pools>();
+    if let Some(pool) = pools.get_mut(target) {
+        if let EffectType::Healing { amount } = heal.effect_type {
+            pool.hit_points.current =
+                i32::min(pool.hit_points.max, pool.hit_points.current + amount);
+        }
+    }
+}
+
+pub fn add_confusion(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Confusion { turns } = effect.effect_type {
+        ecs.write_storage::<Confusion>()
+            .insert(target, Confusion { turns })
+            .expect("Unable to insert status");
+    }
+}
+
+/// Stacks a timed status on the target. Haste and Slow cancel each other instead.
+pub fn add_status(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Status {
+        kind,
+        turns,
+        potency,
+    } = effect.effect_type
+    {
+        let mut statuses = ecs.write_storage::<StatusEffects>();
+        if statuses.get(target).is_none() {
+            statuses
+                .insert(target, StatusEffects::default())
+                .expect("Unable to insert status");
+        }
+        let target_statuses = statuses.get_mut(target).unwrap();
+
+        let cancelled = kind
+            .opposite()
+            .filter(|opposite| target_statuses.remove(*opposite));
+        // Un statut qui se cumule ne change pas les modificateurs
+        let mut changed = true;
+        let message = if let Some(opposite) = cancelled {
+            format!("no longer {}", opposite.name().to_lowercase())
+        } else {
+            changed = target_statuses.add(Status {
+                kind,
+                turns,
+                potency,
+            });
+            kind.name().to_lowercase()
+        };
+        if changed {
+            ecs.write_storage::<StatusChanged>()
+                .insert(target, StatusChanged {})
+                .expect("Unable to insert status changed");
+        }
+
+        if kind == StatusKind::Blind {
+            if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(target) {
+                viewshed.dirty = true;
+            }
+        }
+
+        let subject = if target == *ecs.fetch::<Entity>() {
+            Some("You are".to_string())
+        } else {
+            ecs.read_storage::<Name>()
+                .get(target)
+                .map(|name| format!("{} is", name.name))
+        };
+        if let Some(subject) = subject {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .insert(0, format!("{} {}.", subject, message));
+        }
+    }
+}
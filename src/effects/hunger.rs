@@ -0,0 +1,8 @@
+use super::*;
+
+pub fn well_fed(ecs: &mut World, _effect: &EffectSpawner, target: Entity) {
+    if let Some(hc) = ecs.write_storage::<HungerClock>().get_mut(target) {
+        hc.state = HungerState::WellFed;
+        hc.duration = 20;
+    }
+}
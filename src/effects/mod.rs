@@ -0,0 +1,204 @@
+use super::*;
+use rltk::RGB;
+use std::collections::VecDeque;
+
+mod damage;
+mod hunger;
+mod on_hit;
+mod particles;
+mod targeting;
+mod triggers;
+
+pub use targeting::*;
+
+/// Ce qu'un effet fait, indépendamment de sa source (objet, piège, attaque, sort).
+pub enum EffectType {
+    /// Untyped damage (starvation, bleeding) ignores resistances.
+    Damage {
+        amount: i32,
+        damage_type: Option<DamageType>,
+    },
+    Bloodstain,
+    Particle {
+        glyph: u8,
+        fg: RGB,
+        bg: RGB,
+        lifespan: f32,
+    },
+    EntityDeath,
+    ItemUse {
+        item: Entity,
+    },
+    SpellUse {
+        spell: Entity,
+    },
+    TriggerFire {
+        trigger: Entity,
+    },
+    WellFed,
+    Healing {
+        amount: i32,
+    },
+    Confusion {
+        turns: i32,
+    },
+    Status {
+        kind: StatusKind,
+        turns: i32,
+        potency: i32,
+    },
+    Knockback {
+        distance: i32,
+    },
+    Disarm,
+    MagicMapping,
+}
+
+/// Sur qui, ou sur quoi, l'effet s'applique.
+#[derive(Clone)]
+pub enum Targets {
+    Single {
+        target: Entity,
+    },
+    Tile {
+        tile_idx: i32,
+    },
+    Area {
+        tile_idx: i32,
+        radius: i32,
+    },
+    /// The creator of the effect itself.
+    Creator,
+}
+
+pub struct EffectSpawner {
+    pub creator: Option<Entity>,
+    pub effect_type: EffectType,
+    pub targets: Targets,
+}
+
+/// File des effets à résoudre, vidée par `run_effects_queue` après chaque passe des systèmes.
+#[derive(Default)]
+pub struct EffectQueue {
+    effects: VecDeque<EffectSpawner>,
+}
+
+impl EffectQueue {
+    pub fn new() -> EffectQueue {
+        EffectQueue {
+            effects: VecDeque::new(),
+        }
+    }
+
+    pub fn add_effect(
+        &mut self,
+        creator: Option<Entity>,
+        effect_type: EffectType,
+        targets: Targets,
+    ) {
+        self.effects.push_back(EffectSpawner {
+            creator,
+            effect_type,
+            targets,
+        });
+    }
+}
+
+/// Shortcut for code that holds the whole World rather than the queue resource.
+pub fn add_effect(ecs: &World, creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
+    ecs.fetch_mut::<EffectQueue>()
+        .add_effect(creator, effect_type, targets);
+}
+
+/// Resolves every queued effect, including the ones queued while resolving.
+pub fn run_effects_queue(ecs: &mut World) {
+    loop {
+        let effect = ecs.fetch_mut::<EffectQueue>().effects.pop_front();
+        match effect {
+            None => break,
+            Some(effect) => target_applicator(ecs, &effect),
+        }
+    }
+}
+
+fn target_applicator(ecs: &mut World, effect: &EffectSpawner) {
+    match effect.effect_type {
+        EffectType::ItemUse { item } => {
+            triggers::item_trigger(effect.creator, item, &effect.targets, ecs)
+        }
+        EffectType::SpellUse { spell } => {
+            triggers::spell_trigger(effect.creator, spell, &effect.targets, ecs)
+        }
+        EffectType::TriggerFire { trigger } => {
+            triggers::trigger(effect.creator, trigger, &effect.targets, ecs)
+        }
+        _ => match &effect.targets {
+            Targets::Single { target } => affect_entity(ecs, effect, *target),
+            Targets::Creator => {
+                if let Some(creator) = effect.creator {
+                    affect_entity(ecs, effect, creator);
+                }
+            }
+            Targets::Tile { tile_idx } => affect_tile(ecs, effect, *tile_idx),
+            Targets::Area { tile_idx, radius } => {
+                for tile_idx in area_tiles(ecs, *tile_idx, *radius) {
+                    affect_tile(ecs, effect, tile_idx);
+                }
+            }
+        },
+    }
+}
+
+fn tile_effect_hits_entities(effect: &EffectType) -> bool {
+    matches!(
+        effect,
+        EffectType::Damage { .. }
+            | EffectType::WellFed
+            | EffectType::Healing { .. }
+            | EffectType::Confusion { .. }
+            | EffectType::Status { .. }
+    )
+}
+
+fn affect_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
+    if tile_effect_hits_entities(&effect.effect_type) {
+        let content = ecs.fetch::<Map>().tile_content[tile_idx as usize].clone();
+        for entity in content.iter() {
+            affect_entity(ecs, effect, *entity);
+        }
+    }
+
+    match &effect.effect_type {
+        EffectType::Bloodstain => damage::bloodstain(ecs, tile_idx),
+        EffectType::Particle { .. } => particles::particle_to_tile(ecs, tile_idx, effect),
+        _ => {}
+    }
+}
+
+fn affect_entity(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    match &effect.effect_type {
+        EffectType::Damage { .. } => damage::inflict_damage(ecs, effect, target),
+        EffectType::EntityDeath => damage::death(ecs, effect, target),
+        EffectType::Bloodstain => {
+            if let Some(pos) = entity_position(ecs, target) {
+                damage::bloodstain(ecs, pos)
+            }
+        }
+        EffectType::Particle { .. } => {
+            if let Some(pos) = entity_position(ecs, target) {
+                particles::particle_to_tile(ecs, pos, effect)
+            }
+        }
+        EffectType::WellFed => hunger::well_fed(ecs, effect, target),
+        EffectType::Healing { .. } => damage::heal_damage(ecs, effect, target),
+        EffectType::Confusion { .. } => damage::add_confusion(ecs, effect, target),
+        EffectType::Status { .. } => damage::add_status(ecs, effect, target),
+        EffectType::Knockback { .. } => on_hit::knockback(ecs, effect, target),
+        EffectType::Disarm => on_hit::disarm(ecs, effect, target),
+        EffectType::MagicMapping => {
+            let mut runstate = ecs.fetch_mut::<RunState>();
+            *runstate = RunState::MagicMapReveal { row: 0 };
+        }
+        _ => {}
+    }
+}
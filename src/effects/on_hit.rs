@@ -0,0 +1,97 @@
+use super::*;
+
+/// Pushes the target away from the attacker (the creator), until a wall or a creature stops it.
+pub fn knockback(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Knockback { distance } = effect.effect_type {
+        let mut positions = ecs.write_storage::<Position>();
+        let from = match effect.creator.and_then(|c| positions.get(c)) {
+            None => return,
+            Some(pos) => Point::new(pos.x, pos.y),
+        };
+        let pos = match positions.get_mut(target) {
+            None => return,
+            Some(pos) => pos,
+        };
+        let (dx, dy) = ((pos.x - from.x).signum(), (pos.y - from.y).signum());
+        if dx == 0 && dy == 0 {
+            return;
+        }
+
+        let mut map = ecs.fetch_mut::<Map>();
+        let start_idx = map.xy_idx(pos.x, pos.y);
+        let mut moved = 0;
+        for _ in 0..distance {
+            let (x, y) = (pos.x + dx, pos.y + dy);
+            if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 {
+                break;
+            }
+            let idx = map.xy_idx(x, y);
+            if map.blocked[idx] {
+                break;
+            }
+            pos.x = x;
+            pos.y = y;
+            moved += 1;
+        }
+        if moved == 0 {
+            return;
+        }
+        map.blocked[start_idx] = false;
+        let end_idx = map.xy_idx(pos.x, pos.y);
+        map.blocked[end_idx] = true;
+        let (x, y) = (pos.x, pos.y);
+        std::mem::drop(map);
+        std::mem::drop(positions);
+
+        if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(target) {
+            viewshed.dirty = true;
+        }
+        ecs.write_storage::<EntityMoved>()
+            .insert(target, EntityMoved {})
+            .expect("Unable to insert marker");
+        if target == *ecs.fetch::<Entity>() {
+            *ecs.write_resource::<Point>() = Point::new(x, y);
+        }
+        if let Some(name) = ecs.read_storage::<Name>().get(target) {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .insert(0, format!("{} is knocked back.", name.name));
+        }
+    }
+}
+
+/// The target's melee weapon falls to its feet, unless it is cursed.
+pub fn disarm(ecs: &mut World, _effect: &EffectSpawner, target: Entity) {
+    let pos = match ecs.read_storage::<Position>().get(target) {
+        None => return,
+        Some(pos) => pos.clone(),
+    };
+    let weapon = {
+        let entities = ecs.entities();
+        let equipped = ecs.read_storage::<Equipped>();
+        let cursed = ecs.read_storage::<Cursed>();
+        (&entities, &equipped, !&cursed)
+            .join()
+            .find(|(_, e, _)| e.owner == target && e.slot == EquipmentSlot::Melee)
+            .map(|(weapon, _, _)| weapon)
+    };
+    let weapon = match weapon {
+        None => return,
+        Some(weapon) => weapon,
+    };
+
+    ecs.write_storage::<Equipped>().remove(weapon);
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(target, EquipmentChanged {})
+        .expect("Unable to insert");
+    ecs.write_storage::<Position>()
+        .insert(weapon, pos)
+        .expect("Unable to insert position");
+    let names = ecs.read_storage::<Name>();
+    if let (Some(name), Some(weapon_name)) = (names.get(target), names.get(weapon)) {
+        ecs.fetch_mut::<GameLog>().entries.insert(
+            0,
+            format!("{} is disarmed, the {} falls.", name.name, weapon_name.name),
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+/// Index de la tuile où se trouve l'entité, si elle est sur la carte.
+pub fn entity_position(ecs: &World, target: Entity) -> Option<i32> {
+    if let Some(pos) = ecs.read_storage::<Position>().get(target) {
+        let map = ecs.fetch::<Map>();
+        return Some(map.xy_idx(pos.x, pos.y) as i32);
+    }
+    None
+}
+
+/// Tiles in the line of sight of `tile_idx`, within `radius`, in a stable order.
+pub fn area_tiles(ecs: &World, tile_idx: i32, radius: i32) -> Vec<i32> {
+    let map = ecs.fetch::<Map>();
+    let center = rltk::Point::new(tile_idx % map.width, tile_idx / map.width);
+    let mut blast_tiles = rltk::field_of_view(center, radius, &*map);
+    blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
+    // field_of_view vient d'un HashSet : on trie pour garder un ordre stable
+    blast_tiles.sort_by_key(|p| (p.y, p.x));
+    blast_tiles
+        .iter()
+        .map(|p| map.xy_idx(p.x, p.y) as i32)
+        .collect()
+}
+
+/// The entities with hit points that `targets` reaches.
+pub fn entities_in(ecs: &World, creator: Option<Entity>, targets: &Targets) -> Vec<Entity> {
+    let tiles = match targets {
+        Targets::Single { target } => return vec![*target],
+        Targets::Creator => return creator.into_iter().collect(),
+        Targets::Tile { tile_idx } => vec![*tile_idx],
+        Targets::Area { tile_idx, radius } => area_tiles(ecs, *tile_idx, *radius),
+    };
+
+    let map = ecs.fetch::<Map>();
+    let pools = ecs.read_storage::<Pools>();
+    let mut result: Vec<Entity> = Vec::new();
+    for tile_idx in tiles.iter() {
+        for entity in map.tile_content[*tile_idx as usize].iter() {
+            if pools.get(*entity).is_some() && !result.contains(entity) {
+                result.push(*entity);
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,364 @@
+use super::*;
+
+/// An item was used: applies what it does, then consumes it if it did something.
+pub fn item_trigger(creator: Option<Entity>, item: Entity, targets: &Targets, ecs: &mut World) {
+    let did_something = event_trigger(creator, item, targets, ecs);
+
+    // Utiliser un objet identifie tous ceux du même type
+    if creator == Some(*ecs.fetch::<Entity>()) {
+        let name = ecs.read_storage::<Name>().get(item).map(|n| n.name.clone());
+        if let Some(name) = name {
+            if ecs.fetch_mut::<KnownItems>().identify(&name) {
+                ecs.fetch_mut::<GameLog>()
+                    .entries
+                    .insert(0, format!("It was a {}.", name));
+            }
+        }
+    }
+
+    // Une pile ne disparaît qu'avec son dernier objet
+    if did_something && ecs.read_storage::<Consumable>().get(item).is_some() {
+        let remaining = match ecs.write_storage::<Quantity>().get_mut(item) {
+            Some(qty) => {
+                qty.amount -= 1;
+                qty.amount
+            }
+            None => 0,
+        };
+        if remaining < 1 {
+            ecs.entities().delete(item).expect("Delete failed");
+        }
+        if let Some(owner) = creator {
+            ecs.write_storage::<EquipmentChanged>()
+                .insert(owner, EquipmentChanged {})
+                .expect("Unable to insert");
+        }
+    }
+}
+
+/// A spell was cast: the mana is already paid, the spell stays.
+pub fn spell_trigger(creator: Option<Entity>, spell: Entity, targets: &Targets, ecs: &mut World) {
+    event_trigger(creator, spell, targets, ecs);
+}
+
+/// Something walked on a trap (or any entry trigger).
+pub fn trigger(creator: Option<Entity>, trigger: Entity, targets: &Targets, ecs: &mut World) {
+    // The trap is no longer hidden
+    ecs.write_storage::<Hidden>().remove(trigger);
+    if let Some(name) = ecs.read_storage::<Name>().get(trigger) {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .insert(0, format!("{} triggers!", &name.name));
+    }
+
+    event_trigger(creator, trigger, targets, ecs);
+
+    // If it is single activation, it needs to be removed
+    if ecs
+        .read_storage::<SingleActivation>()
+        .get(trigger)
+        .is_some()
+    {
+        ecs.entities()
+            .delete(trigger)
+            .expect("Unable to delete trap");
+    }
+}
+
+/// Turns the components of an item or a trap into effects. Returns true if it did something.
+fn event_trigger(
+    creator: Option<Entity>,
+    entity: Entity,
+    targets: &Targets,
+    ecs: &mut World,
+) -> bool {
+    let mut did_something = false;
+    let player_entity = *ecs.fetch::<Entity>();
+    let from_player = creator == Some(player_entity);
+    let entity_name = match ecs.read_storage::<Name>().get(entity) {
+        Some(name) => ecs.fetch::<KnownItems>().display_name(&name.name),
+        None => "something".to_string(),
+    };
+
+    // It it is edible, eat it!
+    if ecs.read_storage::<ProvidesFood>().get(entity).is_some() {
+        add_effect(ecs, creator, EffectType::WellFed, targets.clone());
+        if from_player {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .insert(0, format!("You eat the {}.", entity_name));
+        }
+        did_something = true;
+    }
+
+    // Spellbook
+    let teaches = ecs
+        .read_storage::<TeachesSpell>()
+        .get(entity)
+        .map(|book| book.spell.clone());
+    if let (Some(spell), Some(reader)) = (teaches, creator) {
+        did_something |= learn_spell(ecs, reader, &spell);
+    }
+
+    //Magic Mapper
+    if ecs.read_storage::<MagicMapper>().get(entity).is_some() {
+        add_effect(ecs, creator, EffectType::MagicMapping, targets.clone());
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .insert(0, "The map is revealed to you".to_string());
+        did_something = true;
+    }
+
+    // Les effets de zone s'affichent sur toutes les cases touchées
+    if let Targets::Area { .. } = targets {
+        add_effect(
+            ecs,
+            None,
+            EffectType::Particle {
+                glyph: rltk::to_cp437('░'),
+                fg: rltk::RGB::named(rltk::ORANGE),
+                bg: rltk::RGB::named(rltk::BLACK),
+                lifespan: 200.0,
+            },
+            targets.clone(),
+        );
+    }
+
+    let victims = entities_in(ecs, creator, targets);
+
+    // Lève les malédictions de l'équipement des cibles
+    if ecs.read_storage::<RemovesCurse>().get(entity).is_some() {
+        for target in victims.iter() {
+            let freed = remove_curses(ecs, *target);
+            if *target == player_entity {
+                let mut log = ecs.fetch_mut::<GameLog>();
+                if freed.is_empty() {
+                    log.entries
+                        .insert(0, "You feel a gentle warmth, then nothing.".to_string());
+                }
+                for name in freed {
+                    log.entries
+                        .insert(0, format!("The curse on {} is lifted.", name));
+                }
+            }
+        }
+        did_something = true;
+    }
+
+    //Si soin
+    let heal_amount = ecs
+        .read_storage::<ProvidesHealing>()
+        .get(entity)
+        .map(|healer| healer.heal_amount);
+    if let Some(amount) = heal_amount {
+        for target in victims.iter() {
+            let healed = match ecs.read_storage::<Pools>().get(*target) {
+                None => continue,
+                Some(stats) => i32::min(amount, stats.hit_points.max - stats.hit_points.current),
+            };
+            add_effect(
+                ecs,
+                creator,
+                EffectType::Healing { amount },
+                Targets::Single { target: *target },
+            );
+            add_effect(
+                ecs,
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('♥'),
+                    fg: rltk::RGB::named(rltk::GREEN),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *target },
+            );
+            if from_player {
+                let action = if ecs.read_storage::<SpellTemplate>().get(entity).is_some() {
+                    format!("cast {}", entity_name)
+                } else {
+                    format!("drink the {}", entity_name)
+                };
+                ecs.fetch_mut::<GameLog>()
+                    .entries
+                    .insert(0, format!("You {}, healing {} hp.", action, healed.abs()));
+            }
+            did_something = true;
+        }
+    }
+
+    //Si dommage
+    let damage = ecs
+        .read_storage::<InflictsDamage>()
+        .get(entity)
+        .map(|damage| (damage.damage, damage.damage_type));
+    if let Some((amount, damage_type)) = damage {
+        for target in victims.iter() {
+            add_effect(
+                ecs,
+                creator,
+                EffectType::Damage {
+                    amount,
+                    damage_type: Some(damage_type),
+                },
+                Targets::Single { target: *target },
+            );
+            add_effect(
+                ecs,
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('‼'),
+                    fg: rltk::RGB::named(rltk::ORANGE),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *target },
+            );
+            if from_player {
+                let target_name = ecs
+                    .read_storage::<Name>()
+                    .get(*target)
+                    .unwrap()
+                    .name
+                    .clone();
+                ecs.fetch_mut::<GameLog>().entries.insert(
+                    0,
+                    format!(
+                        "You use {} on {}, inflicting {} hp.",
+                        entity_name, target_name, amount
+                    ),
+                );
+            }
+            did_something = true;
+        }
+    }
+
+    //Confusion
+    let confusion = ecs
+        .read_storage::<Confusion>()
+        .get(entity)
+        .map(|confusion| confusion.turns);
+    if let Some(turns) = confusion {
+        for target in victims.iter() {
+            add_effect(
+                ecs,
+                creator,
+                EffectType::Confusion { turns },
+                Targets::Single { target: *target },
+            );
+            add_effect(
+                ecs,
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('?'),
+                    fg: rltk::RGB::named(rltk::MAGENTA),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *target },
+            );
+            if from_player {
+                let target_name = ecs
+                    .read_storage::<Name>()
+                    .get(*target)
+                    .unwrap()
+                    .name
+                    .clone();
+                ecs.fetch_mut::<GameLog>().entries.insert(
+                    0,
+                    format!(
+                        "You use {} on {}, confusing them.",
+                        entity_name, target_name
+                    ),
+                );
+            }
+            did_something = true;
+        }
+    }
+
+    //Statuses (add_status tells the log)
+    let statuses = ecs
+        .read_storage::<InflictsStatus>()
+        .get(entity)
+        .map(|inflicts| inflicts.statuses.clone());
+    if let Some(statuses) = statuses {
+        for target in victims.iter() {
+            for status in statuses.iter() {
+                add_effect(
+                    ecs,
+                    creator,
+                    EffectType::Status {
+                        kind: status.kind,
+                        turns: status.turns,
+                        potency: status.potency,
+                    },
+                    Targets::Single { target: *target },
+                );
+            }
+            add_effect(
+                ecs,
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('!'),
+                    fg: rltk::RGB::named(rltk::YELLOW),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *target },
+            );
+            did_something = true;
+        }
+    }
+
+    did_something
+}
+
+/// `reader` learns `spell` if its Magic skill is high enough. Returns true if it did.
+fn learn_spell(ecs: &mut World, reader: Entity, spell: &str) -> bool {
+    let required = match crate::raws::find_spell_entity(ecs, spell)
+        .and_then(|s| ecs.read_storage::<SpellTemplate>().get(s).cloned())
+    {
+        None => {
+            println!("Warning: spellbook of unknown spell {}.", spell);
+            return false;
+        }
+        Some(template) => template.magic_skill,
+    };
+    let magic = match ecs.read_storage::<Skills>().get(reader) {
+        None => return false,
+        Some(skills) => skill_bonus(Skill::Magic, skills),
+    };
+    let from_player = reader == *ecs.fetch::<Entity>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+
+    if magic < required {
+        if from_player {
+            log.entries.insert(
+                0,
+                format!(
+                    "You can't make sense of {}, it needs a Magic skill of {}.",
+                    spell, required
+                ),
+            );
+        }
+        return false;
+    }
+
+    let mut known_spells = ecs.write_storage::<KnownSpells>();
+    let known = known_spells
+        .entry(reader)
+        .expect("Unable to access known spells")
+        .or_insert_with(KnownSpells::default);
+    if known.spells.iter().any(|s| s == spell) {
+        if from_player {
+            log.entries
+                .insert(0, format!("You already know {}.", spell));
+        }
+        return false;
+    }
+    known.spells.push(spell.to_string());
+    if from_player {
+        log.entries.insert(0, format!("You learn {}.", spell));
+    }
+    true
+}
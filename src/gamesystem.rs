@@ -1,4 +1,5 @@
 use super::components::*;
+use serde::{Deserialize, Serialize};
 
 ///Calc. bonus on stats roll
 /// #Example base roll :
@@ -32,10 +33,366 @@ pub fn mana_at_level(intelligence: i32, level: i32) -> i32 {
     mana_per_level(intelligence) * level
 }
 
+/// Max HP and mana of the player for their level and attributes. What was already
+/// lost stays lost : `current` moves by as much as `max`.
+pub fn recompute_player_pools(pools: &mut Pools, attributes: &Attributes) {
+    let hit_points = player_hp_at_level(
+        attributes.fitness.base + attributes.fitness.modifiers,
+        pools.level,
+    );
+    let mana = mana_at_level(
+        attributes.intelligence.base + attributes.intelligence.modifiers,
+        pools.level,
+    );
+    pools.hit_points.current = i32::max(
+        1,
+        pools.hit_points.current + hit_points - pools.hit_points.max,
+    );
+    pools.hit_points.max = hit_points;
+    pools.mana.current = i32::max(0, pools.mana.current + mana - pools.mana.max);
+    pools.mana.max = mana;
+}
+
+/// Points handed out at each new level.
+pub const ATTRIBUTE_POINTS_PER_LEVEL: i32 = 1;
+pub const SKILL_POINTS_PER_LEVEL: i32 = 2;
+
+/// What a level-up point can be spent on.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum LevelUpChoice {
+    Might,
+    Fitness,
+    Quickness,
+    Intelligence,
+    Skill(Skill),
+}
+
+impl LevelUpChoice {
+    /// In the order of the level-up menu.
+    pub const ALL: [LevelUpChoice; 7] = [
+        LevelUpChoice::Might,
+        LevelUpChoice::Fitness,
+        LevelUpChoice::Quickness,
+        LevelUpChoice::Intelligence,
+        LevelUpChoice::Skill(Skill::Melee),
+        LevelUpChoice::Skill(Skill::Defense),
+        LevelUpChoice::Skill(Skill::Magic),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LevelUpChoice::Might => "Might",
+            LevelUpChoice::Fitness => "Fitness",
+            LevelUpChoice::Quickness => "Quickness",
+            LevelUpChoice::Intelligence => "Intelligence",
+            LevelUpChoice::Skill(Skill::Melee) => "Melee",
+            LevelUpChoice::Skill(Skill::Defense) => "Defense",
+            LevelUpChoice::Skill(Skill::Magic) => "Magic",
+        }
+    }
+
+    /// The current score of what this choice raises.
+    pub fn value(self, attributes: &Attributes, skills: &Skills) -> i32 {
+        match self {
+            LevelUpChoice::Skill(skill) => skills.skills.get(&skill).copied().unwrap_or(0),
+            attribute => attribute.attribute(attributes).base,
+        }
+    }
+
+    fn attribute(self, attributes: &Attributes) -> &Attribute {
+        match self {
+            LevelUpChoice::Might => &attributes.might,
+            LevelUpChoice::Fitness => &attributes.fitness,
+            LevelUpChoice::Quickness => &attributes.quickness,
+            _ => &attributes.intelligence,
+        }
+    }
+
+    /// Spends a point of `points` on this choice. Returns false if there is none left.
+    pub fn apply(
+        self,
+        points: &mut LevelUpPoints,
+        attributes: &mut Attributes,
+        skills: &mut Skills,
+    ) -> bool {
+        match self {
+            LevelUpChoice::Skill(skill) => {
+                if points.skill_points < 1 {
+                    return false;
+                }
+                points.skill_points -= 1;
+                *skills.skills.entry(skill).or_insert(0) += 1;
+            }
+            _ => {
+                if points.attribute_points < 1 {
+                    return false;
+                }
+                points.attribute_points -= 1;
+                let attribute = match self {
+                    LevelUpChoice::Might => &mut attributes.might,
+                    LevelUpChoice::Fitness => &mut attributes.fitness,
+                    LevelUpChoice::Quickness => &mut attributes.quickness,
+                    _ => &mut attributes.intelligence,
+                };
+                attribute.base += 1;
+                attribute.bonus = attr_bonus(attribute.base + attribute.modifiers);
+            }
+        }
+        true
+    }
+}
+
+/// Points to share between the four attributes at character creation. Spread evenly,
+/// they give the 11 everywhere of the default character.
+pub const CREATION_ATTRIBUTE_POINTS: i32 = 12;
+pub const CREATION_MIN_ATTRIBUTE: i32 = 8;
+pub const CREATION_MAX_ATTRIBUTE: i32 = 16;
+
+/// What the character creation screen decides. The replay keeps it to rebuild the
+/// same character.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct CharacterSheet {
+    pub name: String,
+    /// Might, Fitness, Quickness, Intelligence.
+    pub attributes: [i32; 4],
+    pub background: String,
+}
+
+impl CharacterSheet {
+    pub fn new(background: &str) -> CharacterSheet {
+        CharacterSheet {
+            name: String::new(),
+            attributes: [CREATION_MIN_ATTRIBUTE; 4],
+            background: background.to_string(),
+        }
+    }
+
+    pub fn points_left(&self) -> i32 {
+        CREATION_ATTRIBUTE_POINTS
+            - self
+                .attributes
+                .iter()
+                .map(|a| a - CREATION_MIN_ATTRIBUTE)
+                .sum::<i32>()
+    }
+
+    /// Moves attribute `index` by `delta`, within the bounds and the points left.
+    pub fn adjust(&mut self, index: usize, delta: i32) {
+        let value = self.attributes[index] + delta;
+        if (CREATION_MIN_ATTRIBUTE..=CREATION_MAX_ATTRIBUTE).contains(&value)
+            && self.points_left() - delta >= 0
+        {
+            self.attributes[index] = value;
+        }
+    }
+}
+
 pub fn skill_bonus(skill: Skill, skills: &Skills) -> i32 {
+    let modifier = skills.modifiers.get(&skill).copied().unwrap_or(0);
     if skills.skills.contains_key(&skill) {
-        skills.skills[&skill]
+        skills.skills[&skill] + modifier
     } else {
-        -4
+        -4 + modifier
+    }
+}
+
+/// Energy spent by a full action. At `NORMAL_SPEED`, one every ten ticks.
+pub const ACTION_COST: i32 = 100;
+pub const NORMAL_SPEED: i32 = 10;
+/// Even the most burdened, slowed creature still moves.
+pub const MIN_SPEED: i32 = 2;
+/// Armor at least this heavy costs a point of speed.
+pub const HEAVY_ARMOR_LBS: f32 = 20.0;
+/// Carrying more than this many pounds per point of Might slows down.
+pub const CARRY_LBS_PER_MIGHT: f32 = 10.0;
+pub const OVERLOAD_SPEED_PENALTY: i32 = 3;
+
+/// The actions that don't cost a full action (`ACTION_COST`) in energy.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ActionKind {
+    Wade,
+    Shoot,
+    UseItem,
+    PickUp,
+}
+
+impl ActionKind {
+    pub fn cost(self) -> i32 {
+        match self {
+            ActionKind::Wade => ACTION_COST * 3 / 2,
+            ActionKind::Shoot => ACTION_COST * 6 / 5,
+            ActionKind::UseItem => ACTION_COST / 2,
+            ActionKind::PickUp => ACTION_COST / 2,
+        }
+    }
+}
+
+impl Energy {
+    /// A full action is paid when the turn starts : pay for `action` instead.
+    /// Anything not listed in `ActionKind`, like moving or attacking, costs that full action.
+    pub fn spend(&mut self, action: ActionKind) {
+        self.current += ACTION_COST - action.cost();
+    }
+}
+
+/// Energy gained per tick, after quickness, statuses and burden.
+pub fn speed(
+    energy: &Energy,
+    attributes: Option<&Attributes>,
+    statuses: Option<&StatusEffects>,
+) -> i32 {
+    let quickness = attributes.map_or(0, |attr| attr.quickness.bonus);
+    let status = statuses.map_or(0, |s| s.speed_modifier());
+    i32::max(
+        MIN_SPEED,
+        energy.base_speed + quickness + status - energy.burden,
+    )
+}
+
+/// Potency cap for the statuses that intensify.
+pub const MAX_STATUS_POTENCY: i32 = 5;
+
+/// What happens when a status is inflicted on a creature that already has it.
+pub enum StatusStacking {
+    /// Keeps the longest duration and the strongest potency.
+    Refresh,
+    /// Potencies add up (to `MAX_STATUS_POTENCY`), keeps the longest duration.
+    Intensify,
+    /// Durations add up.
+    Extend,
+}
+
+impl StatusKind {
+    /// As shown in the status panel and the log.
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "Poisoned",
+            StatusKind::Burning => "Burning",
+            StatusKind::Bleeding => "Bleeding",
+            StatusKind::Slow => "Slowed",
+            StatusKind::Haste => "Hasted",
+            StatusKind::Blind => "Blind",
+            StatusKind::Regeneration => "Regenerating",
+            StatusKind::Paralysis => "Paralyzed",
+        }
+    }
+
+    pub fn stacking(self) -> StatusStacking {
+        match self {
+            StatusKind::Poison | StatusKind::Bleeding => StatusStacking::Intensify,
+            StatusKind::Regeneration => StatusStacking::Extend,
+            _ => StatusStacking::Refresh,
+        }
+    }
+
+    /// Haste and Slow cancel each other out.
+    pub fn opposite(self) -> Option<StatusKind> {
+        match self {
+            StatusKind::Slow => Some(StatusKind::Haste),
+            StatusKind::Haste => Some(StatusKind::Slow),
+            _ => None,
+        }
+    }
+
+    /// Added to the speed while the status lasts.
+    pub fn speed_modifier(self) -> i32 {
+        match self {
+            StatusKind::Slow => -5,
+            StatusKind::Haste => 5,
+            _ => 0,
+        }
+    }
+
+    /// Added to the quickness modifiers while the status lasts.
+    pub fn quickness_modifier(self) -> i32 {
+        match self {
+            StatusKind::Slow => -2,
+            StatusKind::Haste => 2,
+            StatusKind::Blind => -4,
+            _ => 0,
+        }
+    }
+
+    /// Added to a skill while the status lasts.
+    pub fn skill_modifier(self) -> Option<(Skill, i32)> {
+        match self {
+            StatusKind::Blind => Some((Skill::Melee, -2)),
+            _ => None,
+        }
+    }
+}
+
+impl StatusEffects {
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.statuses.iter().any(|s| s.kind == kind)
+    }
+
+    /// Stacks `status` with the one of the same kind, if any. Returns true if it is new.
+    pub fn add(&mut self, status: Status) -> bool {
+        match self.statuses.iter_mut().find(|s| s.kind == status.kind) {
+            None => {
+                self.statuses.push(status);
+                true
+            }
+            Some(current) => {
+                match status.kind.stacking() {
+                    StatusStacking::Refresh => {
+                        current.turns = i32::max(current.turns, status.turns);
+                        current.potency = i32::max(current.potency, status.potency);
+                    }
+                    StatusStacking::Intensify => {
+                        current.turns = i32::max(current.turns, status.turns);
+                        current.potency =
+                            i32::min(MAX_STATUS_POTENCY, current.potency + status.potency);
+                    }
+                    StatusStacking::Extend => current.turns += status.turns,
+                }
+                false
+            }
+        }
+    }
+
+    /// Returns true if there was such a status.
+    pub fn remove(&mut self, kind: StatusKind) -> bool {
+        let before = self.statuses.len();
+        self.statuses.retain(|s| s.kind != kind);
+        self.statuses.len() != before
+    }
+
+    pub fn speed_modifier(&self) -> i32 {
+        self.statuses.iter().map(|s| s.kind.speed_modifier()).sum()
+    }
+}
+
+impl DamageType {
+    /// As written in the log.
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageType::Slashing => "slashing",
+            DamageType::Piercing => "piercing",
+            DamageType::Bludgeoning => "bludgeoning",
+            DamageType::Fire => "fire",
+            DamageType::Cold => "cold",
+            DamageType::Poison => "poison",
+            DamageType::Magic => "magic",
+        }
+    }
+}
+
+/// Damage left once `resistances` (the creature's own and its worn items') apply :
+/// any immunity cancels it, resisting halves it and vulnerability doubles it.
+/// Resisting and being vulnerable at once cancel out, and nothing stacks.
+pub fn resisted_damage(amount: i32, damage_type: DamageType, resistances: &[&Resistances]) -> i32 {
+    if resistances.iter().any(|r| r.immune.contains(&damage_type)) {
+        return 0;
+    }
+    let resists = resistances.iter().any(|r| r.resist.contains(&damage_type));
+    let vulnerable = resistances
+        .iter()
+        .any(|r| r.vulnerable.contains(&damage_type));
+    match (resists, vulnerable) {
+        (true, false) => amount / 2,
+        (false, true) => amount * 2,
+        _ => amount,
     }
 }
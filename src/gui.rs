@@ -27,6 +27,7 @@ pub enum ItemMenuResult {
 pub enum MenuType {
     Inventory,
     RemoveItem,
+    Spells,
 }
 
 #[derive(PartialEq, Copy, Clone)]
@@ -37,11 +38,15 @@ pub enum GameOverResult {
 
 struct Tooltip {
     lines: Vec<String>,
+    title_color: RGB,
 }
 
 impl Tooltip {
     fn new() -> Tooltip {
-        Tooltip { lines: Vec::new() }
+        Tooltip {
+            lines: Vec::new(),
+            title_color: RGB::named(rltk::WHITE),
+        }
     }
 
     fn add<S: ToString>(&mut self, line: S) {
@@ -80,7 +85,7 @@ impl Tooltip {
             box_gray,
         );
         for (i, s) in self.lines.iter().enumerate() {
-            let col = if i == 0 { white } else { light_gray };
+            let col = if i == 0 { self.title_color } else { light_gray };
             ctx.print_color(coord_x + 1, coord_y + i as i32 + 1, col, black, &s);
         }
     }
@@ -295,6 +300,12 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 
     // Draw stats
     let player_entity = ecs.fetch::<Entity>();
+    if let Some(name) = ecs.read_storage::<Name>().get(*player_entity) {
+        let name_length = name.name.len() as i32;
+        ctx.set(51, 0, box_gray, black, to_cp437('┤'));
+        ctx.set(52 + name_length, 0, box_gray, black, to_cp437('├'));
+        ctx.print_color(52, 0, white, black, &name.name);
+    }
     let pools = ecs.read_storage::<Pools>();
     let player_pools = pools.get(*player_entity).unwrap();
     let health = format!(
@@ -350,9 +361,17 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     let mut y = 9;
     let equipped = ecs.read_storage::<Equipped>();
     let name = ecs.read_storage::<Name>();
-    for (equipped_by, item_name) in (&equipped, &name).join() {
+    let known_items = ecs.fetch::<KnownItems>();
+    let rarities = ecs.read_storage::<Rarity>();
+    for (item, equipped_by, item_name) in (&ecs.entities(), &equipped, &name).join() {
         if equipped_by.owner == *player_entity {
-            ctx.print_color(50, y, white, black, &item_name.name);
+            ctx.print_color(
+                50,
+                y,
+                rarities.get(item).map_or(white, |r| r.color),
+                black,
+                &known_items.display_name(&item_name.name),
+            );
             y += 1;
         }
     }
@@ -361,16 +380,48 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     y += 1;
     let consumables = ecs.read_storage::<Consumable>();
     let backpack = ecs.read_storage::<InBackpack>();
+    let quantities = ecs.read_storage::<Quantity>();
     let mut index = 1;
-    for (carried_by, _consumable, item_name) in (&backpack, &consumables, &name).join() {
+    for (item, carried_by, _consumable, item_name) in
+        (&ecs.entities(), &backpack, &consumables, &name).join()
+    {
         if carried_by.owner == *player_entity && index < 10 {
+            let mut label = known_items.display_name(&item_name.name);
+            if let Some(qty) = quantities.get(item).filter(|q| q.amount > 1) {
+                label = format!("{} ({})", label, qty.amount);
+            }
             ctx.print_color(50, y, yellow, black, &format!("↑{}", index));
-            ctx.print_color(53, y, green, black, &item_name.name);
+            ctx.print_color(53, y, green, black, &label);
             y += 1;
             index += 1;
         }
     }
 
+    // Spells
+    y += 1;
+    let spell_templates = ecs.read_storage::<SpellTemplate>();
+    for (i, spell) in menu_items(ecs, &MenuType::Spells)
+        .iter()
+        .take(9)
+        .enumerate()
+    {
+        let cost = spell_templates.get(*spell).unwrap().mana_cost;
+        let col = if cost <= player_pools.mana.current {
+            RGB::named(rltk::CYAN)
+        } else {
+            RGB::named(rltk::GRAY)
+        };
+        ctx.print_color(50, y, yellow, black, &format!("^{}", i + 1));
+        ctx.print_color(
+            53,
+            y,
+            col,
+            black,
+            &format!("{} ({})", name.get(*spell).unwrap().name, cost),
+        );
+        y += 1;
+    }
+
     // Status
     let hunger = ecs.read_storage::<HungerClock>();
     let hc = hunger.get(*player_entity).unwrap();
@@ -382,6 +433,29 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         HungerState::Hungry => ctx.print_color(50, 44, RGB::named(rltk::ORANGE), black, "Hungry"),
         HungerState::Starving => ctx.print_color(50, 44, red, black, "Starving"),
     }
+    let mut y = 43;
+    if let Some(confusion) = ecs.read_storage::<Confusion>().get(*player_entity) {
+        ctx.print_color(
+            50,
+            y,
+            RGB::named(rltk::MAGENTA),
+            black,
+            &format!("Confused ({})", confusion.turns),
+        );
+        y -= 1;
+    }
+    if let Some(statuses) = ecs.read_storage::<StatusEffects>().get(*player_entity) {
+        for status in statuses.statuses.iter() {
+            ctx.print_color(
+                50,
+                y,
+                status_color(status.kind),
+                black,
+                &format!("{} ({})", status.kind.name(), status.turns),
+            );
+            y -= 1;
+        }
+    }
 
     // Draw the log
     let log = ecs.fetch::<GameLog>();
@@ -396,6 +470,19 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     draw_tooltips(ecs, ctx);
 }
 
+fn status_color(kind: StatusKind) -> RGB {
+    match kind {
+        StatusKind::Poison => RGB::named(rltk::GREEN),
+        StatusKind::Burning => RGB::named(rltk::ORANGE),
+        StatusKind::Bleeding => RGB::named(rltk::RED),
+        StatusKind::Slow => RGB::named(rltk::GREY),
+        StatusKind::Haste => RGB::named(rltk::YELLOW),
+        StatusKind::Blind => RGB::named(rltk::DARK_GREY),
+        StatusKind::Regeneration => RGB::named(rltk::PINK),
+        StatusKind::Paralysis => RGB::named(rltk::CYAN),
+    }
+}
+
 fn draw_attribute(name: &str, attribute: &Attribute, y: i32, ctx: &mut Rltk) {
     use std::cmp::Ordering;
 
@@ -409,20 +496,10 @@ fn draw_attribute(name: &str, attribute: &Attribute, y: i32, ctx: &mut Rltk) {
         Ordering::Equal => white,
         Ordering::Greater => RGB::from_f32(0.0, 1.0, 0.0),
     };
-    /* let color: RGB = if attribute.modifiers < 0 {
-        RGB::from_f32(1.0, 0.0, 0.0)
-    } else if attribute.modifiers == 0 {
-        white
-    } else {
-        RGB::from_f32(0.0, 1.0, 0.0)
-    };*/
-    ctx.print_color(
-        67,
-        y,
-        color,
-        black,
-        &format!("{}", attribute.base + attribute.modifiers),
-    );
+    ctx.print_color(64, y, white, black, &format!("{:>2}", attribute.base));
+    if attribute.modifiers != 0 {
+        ctx.print_color(66, y, color, black, &format!("{:+}", attribute.modifiers));
+    }
     ctx.print_color(73, y, color, black, &format!("{}", attribute.bonus));
     if attribute.bonus > 0 {
         ctx.set(72, y, color, black, rltk::to_cp437('+'));
@@ -437,11 +514,23 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let (min_x, _max_x, min_y, _max_y) = camera::get_screen_bounds(ecs, ctx);
     let map = ecs.fetch::<Map>();
     let names = ecs.read_storage::<Name>();
+    let known_items = ecs.fetch::<KnownItems>();
+    let rarities = ecs.read_storage::<Rarity>();
     let positions = ecs.read_storage::<Position>();
     let hidden = ecs.read_storage::<Hidden>();
     let attributes = ecs.read_storage::<Attributes>();
     let pools = ecs.read_storage::<Pools>();
+    let statuses = ecs.read_storage::<StatusEffects>();
+    let energies = ecs.read_storage::<Energy>();
     let entities = ecs.entities();
+    let player_entity = ecs.fetch::<Entity>();
+    let player_speed = energies.get(*player_entity).map(|energy| {
+        speed(
+            energy,
+            attributes.get(*player_entity),
+            statuses.get(*player_entity),
+        )
+    });
 
     let mouse_pos = ctx.mouse_pos();
     let mut mouse_map_pos = mouse_pos;
@@ -459,7 +548,11 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     for (entity, name, position, _hidden) in (&entities, &names, &positions, !&hidden).join() {
         if position.x == mouse_map_pos.0 && position.y == mouse_map_pos.1 {
             let mut tip = Tooltip::new();
-            tip.add(name.name.to_string());
+            tip.add(known_items.display_name(&name.name));
+            if let Some(rarity) = rarities.get(entity) {
+                tip.title_color = rarity.color;
+                tip.add(&rarity.tier);
+            }
             // Comment on attributes
             let attr = attributes.get(entity);
             if let Some(attr) = attr {
@@ -498,6 +591,21 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
             if let Some(stat) = stat {
                 tip.add(format!("Level: {}", stat.level));
             }
+            // Comment on speed
+            if let (Some(energy), Some(player_speed)) = (energies.get(entity), player_speed) {
+                let my_speed = speed(energy, attributes.get(entity), statuses.get(entity));
+                if entity != *player_entity && my_speed > player_speed {
+                    tip.add("Faster than you");
+                } else if entity != *player_entity && my_speed < player_speed {
+                    tip.add("Slower than you");
+                }
+            }
+            if let Some(statuses) = statuses.get(entity) {
+                let names: Vec<&str> = statuses.statuses.iter().map(|s| s.kind.name()).collect();
+                if !names.is_empty() {
+                    tip.add(names.join(", "));
+                }
+            }
             tip_boxes.push(tip);
         }
     }
@@ -538,33 +646,66 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     }
 }
 
-fn draw_menu<S: ToString>(
-    gs: &mut State,
-    ctx: &mut Rltk,
-    titre: S,
-    menutype: MenuType,
-) -> (ItemMenuResult, Option<Entity>) {
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let names = gs.ecs.read_storage::<Name>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let equipped = gs.ecs.read_storage::<Equipped>();
-    let entities = gs.ecs.entities();
-    let count: usize;
+/// Les objets listés par un menu, dans l'ordre d'affichage (lettre a, b, c...).
+pub fn menu_items(ecs: &World, menutype: &MenuType) -> Vec<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let entities = ecs.entities();
 
     match menutype {
         MenuType::Inventory => {
-            let inventory = (&backpack, &names)
+            let backpack = ecs.read_storage::<InBackpack>();
+            (&entities, &backpack, &names)
                 .join()
-                .filter(|item| item.0.owner == *player_entity);
-            count = inventory.count();
+                .filter(|item| item.1.owner == *player_entity)
+                .map(|item| item.0)
+                .collect()
         }
         MenuType::RemoveItem => {
-            let inventory = (&equipped, &names)
+            let equipped = ecs.read_storage::<Equipped>();
+            (&entities, &equipped, &names)
                 .join()
-                .filter(|item| item.0.owner == *player_entity);
-            count = inventory.count() * 2;
+                .filter(|item| item.1.owner == *player_entity)
+                .map(|item| item.0)
+                .collect()
         }
+        MenuType::Spells => match ecs.read_storage::<KnownSpells>().get(*player_entity) {
+            None => Vec::new(),
+            Some(known) => known
+                .spells
+                .iter()
+                .filter_map(|spell| raws::find_spell_entity(ecs, spell))
+                .collect(),
+        },
     }
+}
+
+/// Position d'un objet dans son menu, utilisée par les commandes joueur.
+pub fn menu_index(ecs: &World, menutype: &MenuType, item: Entity) -> usize {
+    menu_items(ecs, menutype)
+        .iter()
+        .position(|e| *e == item)
+        .expect("Item is not in the menu")
+}
+
+fn draw_menu<S: ToString>(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    titre: S,
+    menutype: MenuType,
+) -> (ItemMenuResult, Option<Entity>) {
+    let names = gs.ecs.read_storage::<Name>();
+    let known_items = gs.ecs.fetch::<KnownItems>();
+    let rarities = gs.ecs.read_storage::<Rarity>();
+    let cursed = gs.ecs.read_storage::<Cursed>();
+    let equipped = gs.ecs.read_storage::<Equipped>();
+    let quantities = gs.ecs.read_storage::<Quantity>();
+    let spells = gs.ecs.read_storage::<SpellTemplate>();
+    let items = menu_items(&gs.ecs, &menutype);
+    let count = match menutype {
+        MenuType::Inventory | MenuType::Spells => items.len(),
+        MenuType::RemoveItem => items.len() * 2,
+    };
 
     let mut y = (25 - (count / 2)) as i32;
     ctx.draw_box(
@@ -590,12 +731,12 @@ fn draw_menu<S: ToString>(
         "ESCAPE to cancel",
     );
 
-    let mut equippable: Vec<Entity> = Vec::new();
     let mut j = 0;
 
     match menutype {
-        MenuType::Inventory => {
-            for (entity, _pack, name) in (&entities, &backpack, &names).join() {
+        MenuType::Inventory | MenuType::Spells => {
+            for entity in items.iter() {
+                let name = known_items.display_name(&names.get(*entity).unwrap().name);
                 ctx.set(
                     17,
                     y,
@@ -618,18 +759,34 @@ fn draw_menu<S: ToString>(
                     rltk::to_cp437(')'),
                 );
 
-                ctx.print(21, y, &name.name.to_string());
-                equippable.push(entity);
+                let label = match (quantities.get(*entity), spells.get(*entity)) {
+                    (Some(qty), _) if qty.amount > 1 => format!("{} ({})", name, qty.amount),
+                    (None, Some(spell)) => format!("{} ({} mana)", name, spell.mana_cost),
+                    _ => name,
+                };
+                ctx.print_color(
+                    21,
+                    y,
+                    rarities
+                        .get(*entity)
+                        .map_or(RGB::named(rltk::WHITE), |r| r.color),
+                    RGB::named(rltk::BLACK),
+                    &label,
+                );
                 y += 1;
                 j += 1;
             }
         }
         MenuType::RemoveItem => {
-            for (entity, pack, name) in (&entities, &equipped, &names).join() {
-                match &pack.slot {
+            for entity in items.iter() {
+                let name = known_items.display_name(&names.get(*entity).unwrap().name);
+                match &equipped.get(*entity).unwrap().slot {
                     EquipmentSlot::Melee => {
                         ctx.print(18, y, "Melee");
                     }
+                    EquipmentSlot::Ranged => {
+                        ctx.print(18, y, "Ranged");
+                    }
                     EquipmentSlot::Shield => {
                         ctx.print(18, y, "Shield");
                     }
@@ -648,6 +805,21 @@ fn draw_menu<S: ToString>(
                     EquipmentSlot::Feet => {
                         ctx.print(18, y, "Feet");
                     }
+                    EquipmentSlot::Neck => {
+                        ctx.print(18, y, "Neck");
+                    }
+                    EquipmentSlot::Ring => {
+                        ctx.print(18, y, "Ring");
+                    }
+                }
+                if cursed.get(*entity).is_some() {
+                    ctx.print_color(
+                        25,
+                        y,
+                        RGB::named(rltk::RED),
+                        RGB::named(rltk::BLACK),
+                        "cursed, can't remove",
+                    );
                 }
 
                 ctx.set(
@@ -672,8 +844,15 @@ fn draw_menu<S: ToString>(
                     rltk::to_cp437(')'),
                 );
 
-                ctx.print(21, y + 1, &name.name.to_string());
-                equippable.push(entity);
+                ctx.print_color(
+                    21,
+                    y + 1,
+                    rarities
+                        .get(*entity)
+                        .map_or(RGB::named(rltk::WHITE), |r| r.color),
+                    RGB::named(rltk::BLACK),
+                    &name,
+                );
                 y += 2;
                 j += 1;
             }
@@ -686,11 +865,8 @@ fn draw_menu<S: ToString>(
             VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
             _ => {
                 let selection = rltk::letter_to_option(key);
-                if selection > -1 && selection < count as i32 {
-                    return (
-                        ItemMenuResult::Selected,
-                        Some(equippable[selection as usize]),
-                    );
+                if selection > -1 && selection < items.len() as i32 {
+                    return (ItemMenuResult::Selected, Some(items[selection as usize]));
                 }
 
                 (ItemMenuResult::NoResponse, None)
@@ -707,10 +883,88 @@ pub fn show_drop_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     draw_menu(gs, ctx, "Drop menu", MenuType::Inventory)
 }
 
+pub enum DropQuantityResult {
+    NoResponse,
+    Cancel,
+    Changed { amount: i32 },
+    Selected { amount: i32 },
+}
+
+/// How many items of a stack to drop: the arrows change the amount, Enter drops them.
+pub fn drop_quantity_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    item: Entity,
+    amount: i32,
+) -> DropQuantityResult {
+    let held = gs
+        .ecs
+        .read_storage::<Quantity>()
+        .get(item)
+        .map_or(1, |q| q.amount);
+    let name = gs
+        .ecs
+        .read_storage::<Name>()
+        .get(item)
+        .map_or(String::new(), |n| {
+            gs.ecs.fetch::<KnownItems>().display_name(&n.name)
+        });
+
+    ctx.draw_box(
+        15,
+        22,
+        31,
+        5,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        22,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Drop how many ?",
+    );
+    ctx.print(18, 24, &name);
+    ctx.print_color(
+        18,
+        25,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        &format!("< {} / {} >", amount, held),
+    );
+    ctx.print_color(
+        18,
+        27,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    match ctx.key {
+        None => DropQuantityResult::NoResponse,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => DropQuantityResult::Cancel,
+            VirtualKeyCode::Left | VirtualKeyCode::Down => DropQuantityResult::Changed {
+                amount: i32::max(1, amount - 1),
+            },
+            VirtualKeyCode::Right | VirtualKeyCode::Up => DropQuantityResult::Changed {
+                amount: i32::min(held, amount + 1),
+            },
+            VirtualKeyCode::Return => DropQuantityResult::Selected { amount },
+            _ => DropQuantityResult::NoResponse,
+        },
+    }
+}
+
 pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     draw_menu(gs, ctx, "Remove which item ?", MenuType::RemoveItem)
 }
 
+pub fn show_cast_spell(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    draw_menu(gs, ctx, "Cast which spell ?", MenuType::Spells)
+}
+
 pub fn ranged_target(
     gs: &mut State,
     ctx: &mut Rltk,
@@ -781,7 +1035,7 @@ pub fn ranged_target(
     (ItemMenuResult::NoResponse, None)
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+pub fn game_over(ctx: &mut Rltk, seed: u64) -> GameOverResult {
     ctx.print_color_centered(
         15,
         RGB::named(rltk::YELLOW),
@@ -800,6 +1054,12 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         RGB::named(rltk::BLACK),
         "That day, sadly, is not in this chapter..",
     );
+    ctx.print_color_centered(
+        19,
+        RGB::named(rltk::GRAY),
+        RGB::named(rltk::BLACK),
+        &format!("Seed : {}", seed),
+    );
 
     ctx.print_color_centered(
         20,
@@ -880,3 +1140,334 @@ pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
         },
     }
 }
+
+pub enum LevelUpMenuResult {
+    NoResponse,
+    Selected { choice: LevelUpChoice },
+}
+
+/// Spends the points of a level-up, one at a time. Can't be cancelled : the menu
+/// comes back until every point is spent.
+pub fn level_up_menu(gs: &mut State, ctx: &mut Rltk) -> LevelUpMenuResult {
+    let player_entity = gs.ecs.fetch::<Entity>();
+    let level_up_points = gs.ecs.read_storage::<LevelUpPoints>();
+    let attributes = gs.ecs.read_storage::<Attributes>();
+    let skills = gs.ecs.read_storage::<Skills>();
+    let points = match level_up_points.get(*player_entity) {
+        None => return LevelUpMenuResult::NoResponse,
+        Some(points) => points,
+    };
+    let player_attributes = attributes.get(*player_entity).unwrap();
+    let player_skills = skills.get(*player_entity).unwrap();
+
+    let count = LevelUpChoice::ALL.len() + 2;
+    let mut y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        31,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Level up!",
+    );
+    ctx.print(
+        18,
+        y,
+        &format!(
+            "{} attribute, {} skill point(s)",
+            points.attribute_points, points.skill_points
+        ),
+    );
+    y += 2;
+
+    for (j, choice) in LevelUpChoice::ALL.iter().enumerate() {
+        let available = match choice {
+            LevelUpChoice::Skill(_) => points.skill_points > 0,
+            _ => points.attribute_points > 0,
+        };
+        let fg = if available {
+            RGB::named(rltk::WHITE)
+        } else {
+            RGB::named(rltk::GRAY)
+        };
+        ctx.set(17, y, fg, RGB::named(rltk::BLACK), rltk::to_cp437('('));
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as u8,
+        );
+        ctx.set(19, y, fg, RGB::named(rltk::BLACK), rltk::to_cp437(')'));
+        ctx.print_color(21, y, fg, RGB::named(rltk::BLACK), choice.name());
+        ctx.print_color(
+            36,
+            y,
+            fg,
+            RGB::named(rltk::BLACK),
+            &format!("{}", choice.value(player_attributes, player_skills)),
+        );
+        y += 1;
+    }
+
+    match ctx.key {
+        None => LevelUpMenuResult::NoResponse,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && (selection as usize) < LevelUpChoice::ALL.len() {
+                LevelUpMenuResult::Selected {
+                    choice: LevelUpChoice::ALL[selection as usize],
+                }
+            } else {
+                LevelUpMenuResult::NoResponse
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CreationStep {
+    Name,
+    Attributes,
+    Background,
+}
+
+/// The character creation screen, kept from one frame to the next.
+pub struct CharacterCreation {
+    pub step: CreationStep,
+    pub selection: usize,
+    pub sheet: CharacterSheet,
+}
+
+impl Default for CharacterCreation {
+    fn default() -> CharacterCreation {
+        let raws = raws::RAWS.lock().unwrap();
+        let background = raws::get_backgrounds(&raws)
+            .first()
+            .map_or(String::new(), |b| b.name.clone());
+        CharacterCreation {
+            step: CreationStep::Name,
+            selection: 0,
+            sheet: CharacterSheet::new(&background),
+        }
+    }
+}
+
+pub enum CharacterCreationResult {
+    NoResponse,
+    Cancel,
+    Done,
+}
+
+const MAX_NAME_LENGTH: usize = 20;
+const ATTRIBUTE_NAMES: [&str; 4] = ["Might", "Fitness", "Quickness", "Intelligence"];
+
+/// Name, then attributes, then background. Escape goes back one step.
+pub fn character_creation(gs: &mut State, ctx: &mut Rltk) -> CharacterCreationResult {
+    let creation = &mut gs.creation;
+
+    ctx.draw_box_double(
+        15,
+        12,
+        49,
+        24,
+        RGB::named(rltk::WHEAT),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color_centered(
+        14,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Create your character",
+    );
+
+    match creation.step {
+        CreationStep::Name => {
+            ctx.print_color_centered(
+                16,
+                RGB::named(rltk::GRAY),
+                RGB::named(rltk::BLACK),
+                "Type a name, then Enter",
+            );
+            ctx.print_color_centered(
+                18,
+                RGB::named(rltk::MAGENTA),
+                RGB::named(rltk::BLACK),
+                &format!("{}_", creation.sheet.name),
+            );
+
+            if let Some(key) = ctx.key {
+                match key {
+                    VirtualKeyCode::Escape => return CharacterCreationResult::Cancel,
+                    VirtualKeyCode::Return => creation.step = CreationStep::Attributes,
+                    VirtualKeyCode::Back => {
+                        creation.sheet.name.pop();
+                    }
+                    VirtualKeyCode::Space => {
+                        if !creation.sheet.name.is_empty()
+                            && creation.sheet.name.len() < MAX_NAME_LENGTH
+                        {
+                            creation.sheet.name.push(' ');
+                        }
+                    }
+                    _ => {
+                        let letter = rltk::letter_to_option(key);
+                        if letter > -1 && creation.sheet.name.len() < MAX_NAME_LENGTH {
+                            let c = (b'a' + letter as u8) as char;
+                            // La première lettre en majuscule
+                            if ctx.shift || creation.sheet.name.is_empty() {
+                                creation.sheet.name.push(c.to_ascii_uppercase());
+                            } else {
+                                creation.sheet.name.push(c);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        CreationStep::Attributes => {
+            ctx.print_color_centered(
+                16,
+                RGB::named(rltk::GRAY),
+                RGB::named(rltk::BLACK),
+                "Up/Down to choose, Left/Right to spend",
+            );
+            ctx.print_color_centered(
+                17,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                &format!("{} point(s) left", creation.sheet.points_left()),
+            );
+            for (i, name) in ATTRIBUTE_NAMES.iter().enumerate() {
+                let fg = if i == creation.selection {
+                    RGB::named(rltk::MAGENTA)
+                } else {
+                    RGB::named(rltk::WHITE)
+                };
+                let y = 19 + i as i32;
+                ctx.print_color(28, y, fg, RGB::named(rltk::BLACK), name);
+                ctx.print_color(
+                    44,
+                    y,
+                    fg,
+                    RGB::named(rltk::BLACK),
+                    &format!("{:>2}", creation.sheet.attributes[i]),
+                );
+            }
+            if creation.sheet.points_left() > 0 {
+                ctx.print_color_centered(
+                    24,
+                    RGB::named(rltk::GRAY),
+                    RGB::named(rltk::BLACK),
+                    "Spend every point, then Enter",
+                );
+            }
+
+            if let Some(key) = ctx.key {
+                match key {
+                    VirtualKeyCode::Escape => {
+                        creation.step = CreationStep::Name;
+                        creation.selection = 0;
+                    }
+                    VirtualKeyCode::Up => {
+                        creation.selection =
+                            (creation.selection + ATTRIBUTE_NAMES.len() - 1) % ATTRIBUTE_NAMES.len()
+                    }
+                    VirtualKeyCode::Down => {
+                        creation.selection = (creation.selection + 1) % ATTRIBUTE_NAMES.len()
+                    }
+                    VirtualKeyCode::Left => creation.sheet.adjust(creation.selection, -1),
+                    VirtualKeyCode::Right => creation.sheet.adjust(creation.selection, 1),
+                    VirtualKeyCode::Return if creation.sheet.points_left() == 0 => {
+                        creation.step = CreationStep::Background;
+                        creation.selection = 0;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        CreationStep::Background => {
+            let raws = raws::RAWS.lock().unwrap();
+            let backgrounds = raws::get_backgrounds(&raws);
+            if backgrounds.is_empty() {
+                return CharacterCreationResult::Done;
+            }
+            ctx.print_color_centered(
+                16,
+                RGB::named(rltk::GRAY),
+                RGB::named(rltk::BLACK),
+                "Choose a background, then Enter",
+            );
+            for (i, background) in backgrounds.iter().enumerate() {
+                let fg = if i == creation.selection {
+                    RGB::named(rltk::MAGENTA)
+                } else {
+                    RGB::named(rltk::WHITE)
+                };
+                ctx.print_color_centered(
+                    18 + i as i32,
+                    fg,
+                    RGB::named(rltk::BLACK),
+                    &background.name,
+                );
+            }
+
+            let chosen = &backgrounds[creation.selection];
+            let mut y = 19 + backgrounds.len() as i32;
+            ctx.print_color_centered(
+                y,
+                RGB::named(rltk::CYAN),
+                RGB::named(rltk::BLACK),
+                &chosen.description,
+            );
+            y += 1;
+            let mut skills: Vec<String> = chosen
+                .skills
+                .iter()
+                .map(|(skill, value)| format!("{} {}", skill, value))
+                .collect();
+            skills.sort();
+            ctx.print_color_centered(
+                y,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                &skills.join(", "),
+            );
+            y += 1;
+            for item in chosen.equipped.iter().chain(chosen.carried.iter()) {
+                ctx.print_color_centered(y, RGB::named(rltk::GRAY), RGB::named(rltk::BLACK), item);
+                y += 1;
+            }
+
+            if let Some(key) = ctx.key {
+                match key {
+                    VirtualKeyCode::Escape => {
+                        creation.step = CreationStep::Attributes;
+                        creation.selection = 0;
+                    }
+                    VirtualKeyCode::Up => {
+                        creation.selection =
+                            (creation.selection + backgrounds.len() - 1) % backgrounds.len()
+                    }
+                    VirtualKeyCode::Down => {
+                        creation.selection = (creation.selection + 1) % backgrounds.len()
+                    }
+                    VirtualKeyCode::Return => {
+                        creation.sheet.background = chosen.name.clone();
+                        return CharacterCreationResult::Done;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    CharacterCreationResult::NoResponse
+}
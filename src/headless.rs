@@ -0,0 +1,152 @@
+use super::*;
+use std::fs;
+
+/// Lit une commande par ligne :
+/// `move dx dy`, `wait`, `pickup`, `use i [x y]`, `drop i [n]`, `remove i`, `fire x y`,
+/// `cast i [x y]`, `reveal`, `teleport`, `levelup i`.
+/// Pour `levelup`, `i` suit l'ordre du menu : Might, Fitness, Quickness, Intelligence,
+/// Melee, Defense, Magic.
+/// Les lignes vides et celles qui commencent par `#` sont ignorées.
+pub fn parse_command(line: &str) -> Option<PlayerCommand> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let numbers: Vec<i32> = words
+        .iter()
+        .skip(1)
+        .filter_map(|w| w.parse().ok())
+        .collect();
+    if numbers.len() != words.len().saturating_sub(1) {
+        return None;
+    }
+
+    match (words.first().copied(), numbers.as_slice()) {
+        (Some("move"), [dx, dy]) => Some(PlayerCommand::Move { dx: *dx, dy: *dy }),
+        (Some("wait"), []) => Some(PlayerCommand::SkipTurn),
+        (Some("pickup"), []) => Some(PlayerCommand::PickUp),
+        (Some("use"), [index]) if *index >= 0 => Some(PlayerCommand::UseItem {
+            index: *index as usize,
+            target: None,
+        }),
+        (Some("use"), [index, x, y]) if *index >= 0 => Some(PlayerCommand::UseItem {
+            index: *index as usize,
+            target: Some(Point::new(*x, *y)),
+        }),
+        (Some("drop"), [index]) if *index >= 0 => Some(PlayerCommand::DropItem {
+            index: *index as usize,
+            amount: None,
+        }),
+        (Some("drop"), [index, amount]) if *index >= 0 => Some(PlayerCommand::DropItem {
+            index: *index as usize,
+            amount: Some(*amount),
+        }),
+        (Some("remove"), [index]) if *index >= 0 => Some(PlayerCommand::RemoveItem {
+            index: *index as usize,
+        }),
+        (Some("fire"), [x, y]) => Some(PlayerCommand::Fire {
+            target: Some(Point::new(*x, *y)),
+        }),
+        (Some("cast"), [index]) if *index >= 0 => Some(PlayerCommand::CastSpell {
+            index: *index as usize,
+            target: None,
+        }),
+        (Some("cast"), [index, x, y]) if *index >= 0 => Some(PlayerCommand::CastSpell {
+            index: *index as usize,
+            target: Some(Point::new(*x, *y)),
+        }),
+        (Some("reveal"), []) => Some(PlayerCommand::RevealMap),
+        (Some("teleport"), []) => Some(PlayerCommand::TeleportToExit),
+        (Some("levelup"), [index]) if *index >= 0 => LevelUpChoice::ALL
+            .get(*index as usize)
+            .map(|choice| PlayerCommand::LevelUp { choice: *choice }),
+        _ => None,
+    }
+}
+
+/// Steps the game without rendering until the player has to act again.
+/// Returns the RunState the game stopped on.
+pub fn run_until_input(gs: &mut State) -> RunState {
+    loop {
+        let runstate = *gs.ecs.fetch::<RunState>();
+        let newrunstate = match runstate {
+            RunState::PreRun
+            | RunState::Ticking
+            | RunState::NextLevel
+            | RunState::PreviousLevel
+            | RunState::MagicMapReveal { .. }
+            | RunState::MapGeneration => gs.advance(runstate),
+            RunState::ShowTargeting { .. }
+            | RunState::ShowFireTargeting { .. }
+            | RunState::ShowSpellTargeting { .. } => {
+                println!("Warning : this command needs a target, command ignored");
+                RunState::AwaitingInput
+            }
+            _ => return runstate,
+        };
+        gs.end_frame(newrunstate);
+    }
+}
+
+/// Reads a script file into commands, skipping the lines it can't understand.
+pub fn load_script(path: &str) -> Vec<PlayerCommand> {
+    let script = fs::read_to_string(path).expect("Unable to read the headless script");
+    let mut commands = Vec::new();
+
+    for (number, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_command(line) {
+            None => println!("Warning : unknown command line {} : {}", number + 1, line),
+            Some(command) => commands.push(command),
+        }
+    }
+    commands
+}
+
+/// Plays `commands` on a new game, without opening a window, then writes the replay
+/// if one is recorded.
+pub fn run_commands(gs: &mut State, commands: &[PlayerCommand]) {
+    gs.mapgen_next_state = Some(RunState::PreRun);
+    let mut runstate = run_until_input(gs);
+    let mut turns = 0;
+
+    for command in commands.iter() {
+        // Les points de niveau peuvent attendre la fin du script
+        if runstate != RunState::AwaitingInput && runstate != RunState::LevelUp {
+            break;
+        }
+        let newrunstate = apply_command(gs, *command);
+        gs.end_frame(newrunstate);
+        runstate = run_until_input(gs);
+        turns += 1;
+    }
+
+    gs.save_replay();
+    print_summary(&gs.ecs, turns, runstate);
+}
+
+fn print_summary(ecs: &World, turns: i32, runstate: RunState) {
+    let player_entity = ecs.fetch::<Entity>();
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let pools = ecs.read_storage::<Pools>();
+    let log = ecs.fetch::<GameLog>();
+
+    println!("Commands played : {}", turns);
+    if runstate == RunState::GameOver {
+        println!("The player is dead");
+    }
+    println!(
+        "Depth {} of {} at ({}, {})",
+        map.depth, map.branch, player_pos.x, player_pos.y
+    );
+    if let Some(stats) = pools.get(*player_entity) {
+        println!(
+            "HP {}/{}, level {}, xp {}",
+            stats.hit_points.current, stats.hit_points.max, stats.level, stats.xp
+        );
+    }
+    for entry in log.entries.iter().take(10).rev() {
+        println!("  {}", entry);
+    }
+}
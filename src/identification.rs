@@ -0,0 +1,78 @@
+use super::raws::{get_unidentified_items, RAWS};
+use rltk::RandomNumberGenerator;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Keeps the names of a run apart from the seed of the levels.
+const NAMES_SEED: u64 = 0x1D3A_7F00;
+
+const SYLLABLES: [&str; 16] = [
+    "XY", "ZZY", "KLA", "ATU", "NOR", "VEX", "QUA", "BLO", "RIM", "ZOT", "FEH", "MUN", "DRA",
+    "OLK", "SPI", "YUR",
+];
+const ADJECTIVES: [&str; 10] = [
+    "murky", "bubbling", "cloudy", "smoky", "fizzy", "oily", "glowing", "thick", "swirling",
+    "dusty",
+];
+const COLORS: [&str; 10] = [
+    "red", "blue", "green", "yellow", "black", "white", "purple", "orange", "pink", "brown",
+];
+
+/// What the player knows of the items of this run: the made-up name of every
+/// item type that needs identifying, and the types already identified.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct KnownItems {
+    pub identified: HashSet<String>,
+    pub obfuscated: HashMap<String, String>,
+}
+
+impl KnownItems {
+    /// Draws the made-up names of the run from its seed, all different.
+    pub fn new(seed: u64) -> KnownItems {
+        let mut rng = RandomNumberGenerator::seeded(seed ^ NAMES_SEED);
+        let mut obfuscated = HashMap::new();
+        let mut used: HashSet<String> = HashSet::new();
+        for (name, kind) in get_unidentified_items(&RAWS.lock().unwrap()) {
+            let mut fake = random_name(&mut rng, &kind);
+            while used.contains(&fake) {
+                fake = random_name(&mut rng, &kind);
+            }
+            used.insert(fake.clone());
+            obfuscated.insert(name, fake);
+        }
+        KnownItems {
+            identified: HashSet::new(),
+            obfuscated,
+        }
+    }
+
+    /// The name to show for `name`: the made-up one until its type is identified.
+    pub fn display_name(&self, name: &str) -> String {
+        match self.obfuscated.get(name) {
+            Some(fake) if !self.identified.contains(name) => fake.clone(),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Identifies every item called `name`. Returns true if it was not known yet.
+    pub fn identify(&mut self, name: &str) -> bool {
+        self.obfuscated.contains_key(name) && self.identified.insert(name.to_string())
+    }
+}
+
+fn random_name(rng: &mut RandomNumberGenerator, kind: &str) -> String {
+    match kind {
+        "potion" => format!(
+            "{} {} potion",
+            ADJECTIVES[rng.roll_dice(1, ADJECTIVES.len() as i32) as usize - 1],
+            COLORS[rng.roll_dice(1, COLORS.len() as i32) as usize - 1]
+        ),
+        _ => {
+            let mut word = String::new();
+            for _ in 0..rng.roll_dice(1, 2) + 1 {
+                word += SYLLABLES[rng.roll_dice(1, SYLLABLES.len() as i32) as usize - 1];
+            }
+            format!("scroll of {}", word)
+        }
+    }
+}
@@ -0,0 +1,689 @@
+use rltk::{Console, GameState, Point, Rltk};
+
+use specs::{
+    prelude::*,
+    saveload::{SimpleMarker, SimpleMarkerAllocator},
+};
+
+pub mod camera;
+pub mod map_builders;
+pub mod raws;
+
+mod ai;
+mod components;
+mod dispatcher;
+mod effects;
+mod gamelog;
+mod gamesystem;
+mod gui;
+pub mod headless;
+mod identification;
+mod map;
+mod player;
+mod random_table;
+mod rect;
+pub mod replay;
+mod rex_assets;
+mod spawner;
+mod system_attributes;
+mod system_damage;
+mod system_encumbrance;
+mod system_hunger;
+mod system_inventory;
+mod system_lighting;
+mod system_map_indexing;
+mod system_melee_combat;
+mod system_particle;
+mod system_ranged_combat;
+mod system_saveload;
+mod system_spell;
+mod system_trigger;
+mod system_visibility;
+
+pub use components::*;
+pub use effects::{EffectQueue, EffectType, Targets};
+pub use gamelog::*;
+pub use gamesystem::*;
+pub use gui::*;
+pub use identification::*;
+pub use map::*;
+pub use player::*;
+pub use random_table::*;
+pub use rect::*;
+pub use spawner::*;
+pub use system_attributes::*;
+pub use system_damage::*;
+pub use system_encumbrance::*;
+pub use system_hunger::*;
+pub use system_inventory::*;
+pub use system_lighting::*;
+pub use system_map_indexing::*;
+pub use system_melee_combat::*;
+pub use system_particle::*;
+pub use system_ranged_combat::*;
+pub use system_spell::*;
+pub use system_trigger::*;
+pub use system_visibility::*;
+
+#[macro_use]
+extern crate lazy_static;
+
+const SHOW_MAPGEN_VISUALIZER: bool = false;
+const SHOW_MAPGEN_VISUALIZER_COLOR: bool = false;
+const MAPGEN_TIMER: f32 = 40.0;
+
+#[macro_use]
+extern crate specs_derive;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum RunState {
+    AwaitingInput,
+    PreRun,
+    Ticking,
+    ShowInventory,
+    ShowDropItem,
+    ShowDropQuantity {
+        item: Entity,
+        amount: i32,
+    },
+    ShowTargeting {
+        range: i32,
+        item: Entity,
+    },
+    ShowFireTargeting {
+        range: i32,
+    },
+    ShowCastSpell,
+    ShowSpellTargeting {
+        range: i32,
+        spell: Entity,
+    },
+    MainMenu {
+        menu_selection: gui::MainMenuSelection,
+    },
+    SaveGame,
+    NextLevel,
+    PreviousLevel,
+    ShowRemoveItem,
+    GameOver,
+    MagicMapReveal {
+        row: i32,
+    },
+    MapGeneration,
+    ShowCheatMenu,
+    LevelUp,
+    CharacterCreation,
+}
+
+pub struct State {
+    pub ecs: World,
+    dispatcher: dispatcher::GameDispatcher,
+    mapgen_next_state: Option<RunState>,
+    mapgen_history: Vec<Map>,
+    mapgen_index: usize,
+    mapgen_timer: f32,
+    fixed_seed: Option<u64>,
+    playback: Option<replay::Playback>,
+    creation: gui::CharacterCreation,
+    /// Where the replay is written on save and on death, none if not asked for.
+    record_path: Option<String>,
+}
+
+impl State {
+    fn run_systems(&mut self) {
+        self.dispatcher.run(&mut self.ecs);
+    }
+}
+
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut Rltk) {
+        let mut newrunstate;
+        {
+            let runstate = self.ecs.fetch::<RunState>();
+            newrunstate = *runstate;
+        }
+        ctx.cls(); //cls = Clear the screen
+        system_particle::cull_dead_particles(&mut self.ecs, ctx.frame_time_ms);
+
+        //Permet de ne pas créer la map temps que dans le menu principale
+        match newrunstate {
+            RunState::MainMenu { .. } => {}
+            RunState::GameOver => {}
+            RunState::CharacterCreation => {}
+            _ => {
+                camera::render_camera(&self.ecs, ctx);
+                gui::draw_ui(&self.ecs, ctx);
+            }
+        }
+
+        match newrunstate {
+            RunState::ShowCheatMenu => {
+                let result = gui::show_cheat_mode(self, ctx);
+                match result {
+                    gui::CheatMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::CheatMenuResult::NoResponse => {}
+                    gui::CheatMenuResult::TeleportToExit => {
+                        newrunstate = apply_command(self, PlayerCommand::TeleportToExit);
+                    }
+                }
+            }
+            RunState::MapGeneration => {
+                if !SHOW_MAPGEN_VISUALIZER {
+                    newrunstate = self.mapgen_next_state.unwrap();
+                }
+                ctx.cls();
+                //draw_map(&self.mapgen_history[self.mapgen_index], ctx);
+                if self.mapgen_index < self.mapgen_history.len() {
+                    camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx);
+                }
+
+                self.mapgen_timer += ctx.frame_time_ms;
+                if self.mapgen_timer > MAPGEN_TIMER {
+                    self.mapgen_timer = 0.0;
+                    self.mapgen_index += 1;
+                    if self.mapgen_index == self.mapgen_history.len() {
+                        //loop {println!("loop");}
+                        newrunstate = self.mapgen_next_state.unwrap();
+                    }
+                }
+            }
+            RunState::AwaitingInput => {
+                if self.playback.is_some() {
+                    newrunstate = replay::playback_input(self, ctx);
+                } else {
+                    newrunstate = player_input(self, ctx);
+                }
+            }
+            RunState::LevelUp => {
+                if self.playback.is_some() {
+                    newrunstate = replay::playback_input(self, ctx);
+                } else {
+                    match gui::level_up_menu(self, ctx) {
+                        gui::LevelUpMenuResult::NoResponse => {}
+                        gui::LevelUpMenuResult::Selected { choice } => {
+                            newrunstate = apply_command(self, PlayerCommand::LevelUp { choice });
+                        }
+                    }
+                }
+            }
+            RunState::ShowInventory => {
+                let result = gui::show_inventory(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let index =
+                            gui::menu_index(&self.ecs, &MenuType::Inventory, result.1.unwrap());
+                        newrunstate = apply_command(
+                            self,
+                            PlayerCommand::UseItem {
+                                index,
+                                target: None,
+                            },
+                        );
+                    }
+                }
+            }
+            RunState::ShowDropItem => {
+                let result = gui::show_drop_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = result.1.unwrap();
+                        let held = self
+                            .ecs
+                            .read_storage::<Quantity>()
+                            .get(item)
+                            .map_or(1, |q| q.amount);
+                        if held > 1 {
+                            newrunstate = RunState::ShowDropQuantity { item, amount: held };
+                        } else {
+                            let index = gui::menu_index(&self.ecs, &MenuType::Inventory, item);
+                            newrunstate = apply_command(
+                                self,
+                                PlayerCommand::DropItem {
+                                    index,
+                                    amount: None,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            RunState::ShowDropQuantity { item, amount } => {
+                match gui::drop_quantity_menu(self, ctx, item, amount) {
+                    gui::DropQuantityResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::DropQuantityResult::NoResponse => {}
+                    gui::DropQuantityResult::Changed { amount } => {
+                        newrunstate = RunState::ShowDropQuantity { item, amount }
+                    }
+                    gui::DropQuantityResult::Selected { amount } => {
+                        let index = gui::menu_index(&self.ecs, &MenuType::Inventory, item);
+                        newrunstate = apply_command(
+                            self,
+                            PlayerCommand::DropItem {
+                                index,
+                                amount: Some(amount),
+                            },
+                        );
+                    }
+                }
+            }
+            RunState::ShowTargeting { range, item } => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let index = gui::menu_index(&self.ecs, &MenuType::Inventory, item);
+                        newrunstate = apply_command(
+                            self,
+                            PlayerCommand::UseItem {
+                                index,
+                                target: result.1,
+                            },
+                        );
+                    }
+                }
+            }
+            RunState::ShowFireTargeting { range } => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = apply_command(self, PlayerCommand::Fire { target: result.1 });
+                    }
+                }
+            }
+            RunState::ShowCastSpell => {
+                let result = gui::show_cast_spell(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let index =
+                            gui::menu_index(&self.ecs, &MenuType::Spells, result.1.unwrap());
+                        newrunstate = apply_command(
+                            self,
+                            PlayerCommand::CastSpell {
+                                index,
+                                target: None,
+                            },
+                        );
+                    }
+                }
+            }
+            RunState::ShowSpellTargeting { range, spell } => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let index = gui::menu_index(&self.ecs, &MenuType::Spells, spell);
+                        newrunstate = apply_command(
+                            self,
+                            PlayerCommand::CastSpell {
+                                index,
+                                target: result.1,
+                            },
+                        );
+                    }
+                }
+            }
+            RunState::MainMenu { .. } => {
+                let result = gui::main_menu(self, ctx);
+                match result {
+                    gui::MainMenuResult::NoSelection { selected } => {
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: selected,
+                        }
+                    }
+                    gui::MainMenuResult::Selected { selected } => match selected {
+                        gui::MainMenuSelection::NewGame => {
+                            self.creation = gui::CharacterCreation::default();
+                            newrunstate = RunState::CharacterCreation;
+                        }
+                        gui::MainMenuSelection::LoadGame => {
+                            system_saveload::load_game(&mut self.ecs);
+                            newrunstate = RunState::AwaitingInput;
+                            system_saveload::delete_save();
+                        }
+                        gui::MainMenuSelection::Quit => ::std::process::exit(0),
+                    },
+                }
+            }
+            RunState::CharacterCreation => match gui::character_creation(self, ctx) {
+                gui::CharacterCreationResult::NoResponse => {}
+                gui::CharacterCreationResult::Cancel => {
+                    newrunstate = RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::NewGame,
+                    }
+                }
+                gui::CharacterCreationResult::Done => {
+                    let sheet = self.creation.sheet.clone();
+                    self.create_character(&sheet);
+                    newrunstate = RunState::PreRun;
+                }
+            },
+            RunState::ShowRemoveItem => {
+                let result = gui::remove_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let index =
+                            gui::menu_index(&self.ecs, &MenuType::RemoveItem, result.1.unwrap());
+                        newrunstate = apply_command(self, PlayerCommand::RemoveItem { index });
+                    }
+                }
+            }
+            RunState::GameOver => {
+                let seed = self.ecs.fetch::<map::MasterDungeonMap>().seed;
+                let result = gui::game_over(ctx, seed);
+                match result {
+                    gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::QuitToMenu => {
+                        self.save_replay();
+                        self.game_over_cleanup();
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::NewGame,
+                        };
+                    }
+                }
+            }
+            _ => newrunstate = self.advance(newrunstate),
+        }
+
+        self.end_frame(newrunstate);
+    }
+}
+
+impl State {
+    /// Writes the replay of the game to `path` on save, on death and at the end
+    /// of a headless run.
+    pub fn record_to(&mut self, path: &str) {
+        self.record_path = Some(path.to_string());
+    }
+
+    fn save_replay(&self) {
+        if let Some(path) = &self.record_path {
+            self.ecs.fetch::<replay::Replay>().save(path);
+        }
+    }
+
+    /// Processes one step of every RunState that doesn't wait on the player,
+    /// and returns the state to switch to. Needs no Rltk context, so it can be
+    /// driven either by `tick` or by the headless runner.
+    fn advance(&mut self, runstate: RunState) -> RunState {
+        match runstate {
+            RunState::MapGeneration => self.mapgen_next_state.unwrap(),
+            RunState::PreRun => {
+                self.run_systems();
+                RunState::AwaitingInput
+            }
+            RunState::Ticking => {
+                let mut newrunstate = runstate;
+                while newrunstate == RunState::Ticking {
+                    self.run_systems();
+                    self.ecs.maintain();
+                    match *self.ecs.fetch::<RunState>() {
+                        RunState::AwaitingInput if pending_level_up(&self.ecs) => {
+                            newrunstate = RunState::LevelUp
+                        }
+                        RunState::AwaitingInput => newrunstate = RunState::AwaitingInput,
+                        RunState::MagicMapReveal { .. } => {
+                            newrunstate = RunState::MagicMapReveal { row: 0 }
+                        }
+                        _ => newrunstate = RunState::Ticking,
+                    }
+                }
+                newrunstate
+            }
+            RunState::SaveGame => {
+                system_saveload::save_game(&mut self.ecs);
+                self.save_replay();
+
+                RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::LoadGame,
+                }
+            }
+            RunState::NextLevel => {
+                self.goto_level(1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            RunState::PreviousLevel => {
+                self.goto_level(-1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            RunState::MagicMapReveal { row } => {
+                let mut map = self.ecs.fetch_mut::<Map>();
+                for x in 0..map.width {
+                    let idx = map.xy_idx(x, row);
+                    map.revealed_tiles[idx] = true;
+                }
+                if row == map.height - 2 {
+                    RunState::Ticking
+                } else {
+                    RunState::MagicMapReveal { row: row + 1 }
+                }
+            }
+            _ => runstate,
+        }
+    }
+
+    /// Stores the new RunState and removes whatever died during the frame.
+    fn end_frame(&mut self, newrunstate: RunState) {
+        {
+            let mut runwriter = self.ecs.write_resource::<RunState>();
+            *runwriter = newrunstate;
+        }
+        system_damage::delete_the_dead(&mut self.ecs);
+    }
+}
+
+impl State {
+    /// Moves the player `offset` levels down (or up), on a new or known map.
+    /// An `offset` of 0 generates the first level of a new run.
+    fn generate_world_map(&mut self, offset: i32) {
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+        self.mapgen_history.clear();
+        let map_building_info = map::level_transition(&mut self.ecs, offset);
+        if let Some(history) = map_building_info {
+            self.mapgen_history = history;
+        } else {
+            map::thaw_level_entities(&mut self.ecs);
+        }
+    }
+
+    fn goto_level(&mut self, offset: i32) {
+        freeze_level_entities(&mut self.ecs);
+
+        // Build a new map and place the player
+        self.generate_world_map(offset);
+
+        // Notify the player
+        let mut gamelog = self.ecs.fetch_mut::<gamelog::GameLog>();
+        gamelog.entries.insert(0, "You change level.".to_string());
+    }
+
+    fn game_over_cleanup(&mut self) {
+        //Delete everything
+        let mut to_delete = Vec::new();
+        for e in self.ecs.entities().join() {
+            to_delete.push(e);
+        }
+        for del in to_delete.iter() {
+            self.ecs.delete_entity(*del).expect("Deletion failed");
+        }
+
+        //spawn new player
+        {
+            let player_entity = spawner::player(&mut self.ecs, 0, 0);
+            let mut player_entity_writer = self.ecs.write_resource::<Entity>();
+            *player_entity_writer = player_entity;
+        }
+        raws::spawn_all_spells(&raws::RAWS.lock().unwrap(), &mut self.ecs);
+
+        // Replace the world maps
+        let seed = self.new_seed();
+        self.start_run(seed);
+
+        // Build a new map and place the player
+        self.generate_world_map(0);
+    }
+}
+
+impl State {
+    /// The seed given with `--seed`, or a random one.
+    fn new_seed(&self) -> u64 {
+        match self.fixed_seed {
+            Some(seed) => seed,
+            None => rltk::RandomNumberGenerator::new().next_u64(),
+        }
+    }
+
+    /// Starts the game on `replay`, without going through the main menu.
+    pub fn start_playback(&mut self, replay: &replay::Replay) {
+        if let Some(sheet) = &replay.character {
+            self.create_character(sheet);
+        }
+        self.playback = Some(replay::Playback::new(replay));
+        self.mapgen_next_state = Some(RunState::PreRun);
+    }
+
+    /// Gives the player the character of `sheet`, and keeps it for the replay.
+    pub fn create_character(&mut self, sheet: &CharacterSheet) {
+        spawner::apply_character(&mut self.ecs, sheet);
+        self.ecs.fetch_mut::<replay::Replay>().character = Some(sheet.clone());
+    }
+
+    /// Resets the RNG and the dungeon for a run played with `seed`.
+    fn start_run(&mut self, seed: u64) {
+        self.ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
+        self.ecs.insert(map::MasterDungeonMap::new(seed));
+        self.ecs.insert(KnownItems::new(seed));
+        self.ecs.insert(replay::Replay::new(seed));
+        self.playback = None;
+    }
+
+    /// Creates the ECS world with every component and resource registered,
+    /// and builds the first level. Opens no window.
+    pub fn new(fixed_seed: Option<u64>) -> State {
+        let mut gs = State {
+            ecs: World::new(),
+            dispatcher: dispatcher::GameDispatcher::new(),
+            mapgen_next_state: Some(RunState::MainMenu {
+                menu_selection: gui::MainMenuSelection::NewGame,
+            }),
+            mapgen_index: 0,
+            mapgen_history: Vec::new(),
+            mapgen_timer: 0.0,
+            fixed_seed,
+            playback: None,
+            creation: gui::CharacterCreation::default(),
+            record_path: None,
+        };
+
+        //Permet au systeme ECS de connaître les components disponible
+        //Et crée systeme de stock pour chacun
+        gs.ecs.register::<Position>();
+        gs.ecs.register::<Renderable>();
+        gs.ecs.register::<Player>();
+        gs.ecs.register::<Viewshed>();
+        gs.ecs.register::<Quips>();
+        gs.ecs.register::<Name>();
+        gs.ecs.register::<BlocksTile>();
+        gs.ecs.register::<WantsToMelee>();
+        gs.ecs.register::<WantsToShoot>();
+        gs.ecs.register::<RangedWeapon>();
+        gs.ecs.register::<Ammunition>();
+        gs.ecs.register::<Quantity>();
+        gs.ecs.register::<Item>();
+        gs.ecs.register::<ProvidesHealing>();
+        gs.ecs.register::<InBackpack>();
+        gs.ecs.register::<WantsToPickupItem>();
+        gs.ecs.register::<WantsToUseItem>();
+        gs.ecs.register::<WantsToDropItem>();
+        gs.ecs.register::<Consumable>();
+        gs.ecs.register::<Ranged>();
+        gs.ecs.register::<InflictsDamage>();
+        gs.ecs.register::<AreaOfEffect>();
+        gs.ecs.register::<Targetable>();
+        gs.ecs.register::<Confusion>();
+        gs.ecs.register::<StatusEffects>();
+        gs.ecs.register::<InflictsStatus>();
+        gs.ecs.register::<SpellTemplate>();
+        gs.ecs.register::<KnownSpells>();
+        gs.ecs.register::<TeachesSpell>();
+        gs.ecs.register::<WantsToCastSpell>();
+        gs.ecs.register::<Resistances>();
+        gs.ecs.register::<SimpleMarker<SerializeMe>>();
+        gs.ecs.register::<DMSerializationHelper>();
+        gs.ecs.register::<SerializationHelper>();
+        gs.ecs.register::<Equippable>();
+        gs.ecs.register::<Equipped>();
+        gs.ecs.register::<MeleeWeapon>();
+        gs.ecs.register::<Wearable>();
+        gs.ecs.register::<WantsToRemoveItem>();
+        gs.ecs.register::<ParticleLifetime>();
+        gs.ecs.register::<HungerClock>();
+        gs.ecs.register::<ProvidesFood>();
+        gs.ecs.register::<MagicMapper>();
+        gs.ecs.register::<Hidden>();
+        gs.ecs.register::<EntryTrigger>();
+        gs.ecs.register::<EntityMoved>();
+        gs.ecs.register::<SingleActivation>();
+        gs.ecs.register::<BlocksVisibility>();
+        gs.ecs.register::<Door>();
+        gs.ecs.register::<Attributes>();
+        gs.ecs.register::<Skills>();
+        gs.ecs.register::<LevelUpPoints>();
+        gs.ecs.register::<Pools>();
+        gs.ecs.register::<NaturalAttackDefense>();
+        gs.ecs.register::<LootTable>();
+        gs.ecs.register::<OtherLevelPosition>();
+        gs.ecs.register::<LightSource>();
+        gs.ecs.register::<Energy>();
+        gs.ecs.register::<EquipmentChanged>();
+        gs.ecs.register::<Rarity>();
+        gs.ecs.register::<Cursed>();
+        gs.ecs.register::<RemovesCurse>();
+        gs.ecs.register::<StatusChanged>();
+        gs.ecs.register::<AttributeModifiers>();
+        gs.ecs.register::<SkillModifiers>();
+        gs.ecs.register::<MyTurn>();
+        gs.ecs.register::<Faction>();
+        gs.ecs.register::<WantsToApproach>();
+        gs.ecs.register::<WantsToFlee>();
+        gs.ecs.register::<MoveMode>();
+        gs.ecs.register::<Chasing>();
+        gs.ecs.register::<ChaseSettings>();
+
+        gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+
+        raws::load_raws();
+        let seed = gs.new_seed();
+        gs.start_run(seed);
+        gs.ecs.insert(Map::new(1, 64, 64, "New Map"));
+        gs.ecs.insert(Point::new(0, 0));
+        let player_entity = spawner::player(&mut gs.ecs, 0, 0);
+        gs.ecs.insert(player_entity);
+        raws::spawn_all_spells(&raws::RAWS.lock().unwrap(), &mut gs.ecs);
+        gs.ecs.insert(RunState::MapGeneration);
+        gs.ecs.insert(gamelog::GameLog {
+            entries: vec!["Welcome to Rusty Roguelike".to_string()],
+        });
+        gs.ecs.insert(system_particle::ParticleBuilder::new());
+        gs.ecs.insert(effects::EffectQueue::new());
+        gs.ecs.insert(rex_assets::RexAssets::new());
+
+        gs.generate_world_map(0);
+
+        gs
+    }
+}
@@ -1,141 +1,353 @@
-use super::{Map, TileType};
+use super::{Map, TileType, MAIN_BRANCH};
 use crate::{
     components::{OtherLevelPosition, Position, Viewshed},
-    map_builders::level_builder,
+    map_builders::{branch_entrance, build_level},
 };
 use rltk::Point;
 use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use std::collections::HashMap;
 
+/// A level of the dungeon: a branch, and a depth in it.
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
+pub struct LevelKey {
+    pub branch: String,
+    pub depth: i32,
+}
+
+impl LevelKey {
+    pub fn new<S: ToString>(branch: S, depth: i32) -> LevelKey {
+        LevelKey {
+            branch: branch.to_string(),
+            depth,
+        }
+    }
+
+    /// Key of the `maps` table: JSON only has string keys.
+    fn id(&self) -> String {
+        format!("{}:{}", self.branch, self.depth)
+    }
+}
+
+impl Map {
+    pub fn key(&self) -> LevelKey {
+        LevelKey::new(&self.branch, self.depth)
+    }
+}
+
+/// One end of a staircase: a level and a tile index on it.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct StairEnd {
+    pub level: LevelKey,
+    pub idx: usize,
+}
+
+/// Taking the staircase at `from` leads to `to`.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct StairLink {
+    pub from: StairEnd,
+    pub to: StairEnd,
+}
+
+/// A staircase going down into another branch, not taken yet.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct BranchStair {
+    pub stair: StairEnd,
+    pub to: LevelKey,
+}
+
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct MasterDungeonMap {
-    maps: HashMap<i32, Map>,
+    maps: HashMap<String, Map>,
+    #[serde(default)]
+    stairs: Vec<StairLink>,
+    #[serde(default)]
+    branch_stairs: Vec<BranchStair>,
+    pub seed: u64,
 }
 
 impl MasterDungeonMap {
-    pub fn new() -> MasterDungeonMap {
+    pub fn new(seed: u64) -> MasterDungeonMap {
         MasterDungeonMap {
             maps: HashMap::new(),
+            stairs: Vec::new(),
+            branch_stairs: Vec::new(),
+            seed,
         }
     }
 
+    /// Each level gets its own seed, so a level only depends on the master seed
+    /// and not on what the player did on the levels before it.
+    pub fn level_seed(&self, level: &LevelKey) -> u64 {
+        let mut seed = self.seed ^ (level.depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        if level.branch != MAIN_BRANCH {
+            // FNV-1a : the same on every platform, unlike the std hashers
+            let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+            for byte in level.branch.bytes() {
+                hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+            }
+            seed ^= hash;
+        }
+        seed
+    }
+
     pub fn store_map(&mut self, map: &Map) {
-        self.maps.insert(map.depth, map.clone());
+        self.maps.insert(map.key().id(), map.clone());
     }
 
-    pub fn get_map(&self, depth: i32) -> Option<Map> {
-        if self.maps.contains_key(&depth) {
-            let mut result = self.maps[&depth].clone();
+    pub fn get_map(&self, level: &LevelKey) -> Option<Map> {
+        self.maps.get(&level.id()).map(|map| {
+            let mut result = map.clone();
             result.tile_content = vec![Vec::new(); (result.width * result.height) as usize];
-            Some(result)
-        } else {
-            None
-        }
+            result
+        })
     }
-}
 
-pub fn level_transition(ecs: &mut World, new_depth: i32, offset: i32) -> Option<Vec<Map>> {
-    // Obtain the master dungeon map
-    let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
+    /// Where the staircase at `from` leads, if it has already been taken.
+    pub fn stair_destination(&self, from: &StairEnd) -> Option<StairEnd> {
+        self.stairs
+            .iter()
+            .find(|l| l.from == *from)
+            .map(|l| l.to.clone())
+    }
 
-    // Do we already have a map?
-    if dungeon_master.get_map(new_depth).is_some() {
-        std::mem::drop(dungeon_master);
-        transition_to_existing_map(ecs, new_depth, offset);
-        None
+    /// Connects two staircases, in both directions.
+    pub fn link_stairs(&mut self, a: StairEnd, b: StairEnd) {
+        self.stairs
+            .retain(|l| l.from != a && l.from != b && l.to != a && l.to != b);
+        self.stairs.push(StairLink {
+            from: a.clone(),
+            to: b.clone(),
+        });
+        self.stairs.push(StairLink { from: b, to: a });
+    }
+
+    fn is_linked(&self, end: &StairEnd) -> bool {
+        self.stairs.iter().any(|l| l.from == *end)
+    }
+
+    pub fn add_branch_stair(&mut self, stair: StairEnd, to: LevelKey) {
+        self.branch_stairs.push(BranchStair { stair, to });
+    }
+
+    /// The branch level the staircase at `stair` goes down to, if it is a branch entrance.
+    pub fn branch_destination(&self, stair: &StairEnd) -> Option<LevelKey> {
+        self.branch_stairs
+            .iter()
+            .find(|b| b.stair == *stair)
+            .map(|b| b.to.clone())
+    }
+}
+
+/// The staircase the player is taking: the one under the player, or else the first
+/// one of the right kind that stays in the branch (when the player teleports to the exit).
+fn departure_stair(ecs: &World, offset: i32) -> Option<StairEnd> {
+    let stair_type = if offset > 0 {
+        TileType::DownStairs
     } else {
-        std::mem::drop(dungeon_master);
-        Some(transition_to_new_map(ecs, new_depth))
+        TileType::UpStairs
+    };
+    let map = ecs.fetch::<Map>();
+    let dungeon_master = ecs.fetch::<MasterDungeonMap>();
+    let player_pos = ecs.fetch::<Point>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    let stair_at = |idx: usize| StairEnd {
+        level: map.key(),
+        idx,
+    };
+    if map.tiles[player_idx] == stair_type {
+        return Some(stair_at(player_idx));
     }
+    map.tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| **t == stair_type)
+        .map(|(idx, _)| stair_at(idx))
+        .find(|stair| dungeon_master.branch_destination(stair).is_none())
 }
 
-fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
-    let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
-    let mut builder = level_builder(new_depth, &mut rng, 80, 50);
-    builder.build_map(&mut rng);
-    if new_depth > 1 {
-        if let Some(pos) = &builder.build_data.starting_position {
-            let up_idx = builder.build_data.map.xy_idx(pos.x, pos.y);
-            builder.build_data.map.tiles[up_idx] = TileType::UpStairs;
+/// The level reached by taking the stairs `from`, in the direction `offset`.
+fn destination(ecs: &World, offset: i32, from: &Option<StairEnd>) -> LevelKey {
+    let dungeon_master = ecs.fetch::<MasterDungeonMap>();
+    if let Some(from) = from {
+        if let Some(to) = dungeon_master.stair_destination(from) {
+            return to.level;
+        }
+        if let Some(to) = dungeon_master.branch_destination(from) {
+            return to;
         }
     }
-    let mapgen_history = builder.build_data.history.clone();
-    let player_start;
-    {
-        let mut worldmap_resource = ecs.write_resource::<Map>();
-        *worldmap_resource = builder.build_data.map.clone();
-        player_start = builder
-            .build_data
-            .starting_position
-            .as_mut()
-            .unwrap()
-            .clone();
+    let here = ecs.fetch::<Map>().key();
+    if offset < 0 {
+        if let Some(entrance) = branch_entrance(&here) {
+            return entrance;
+        }
     }
+    LevelKey::new(here.branch, here.depth + offset)
+}
 
-    // Spawn bad guys
-    std::mem::drop(rng);
-    builder.spawn_entities(ecs);
+/// The staircase the player arrives on when coming from a new staircase:
+/// a free one of the right kind, or the first one.
+fn arrival_stair(dungeon_master: &MasterDungeonMap, map: &Map, offset: i32) -> Option<usize> {
+    let stair_type = if offset < 0 {
+        TileType::DownStairs
+    } else {
+        TileType::UpStairs
+    };
+    let mut stairs = map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| **t == stair_type)
+        .map(|(idx, _)| idx);
+    let first = stairs.next()?;
+    let free = std::iter::once(first).chain(stairs).find(|idx| {
+        !dungeon_master.is_linked(&StairEnd {
+            level: map.key(),
+            idx: *idx,
+        })
+    });
+    Some(free.unwrap_or(first))
+}
 
-    // Place the player and update resources
-    let (player_x, player_y) = (player_start.x, player_start.y);
+fn place_player(ecs: &mut World, x: i32, y: i32) {
     let mut player_position = ecs.write_resource::<Point>();
-    *player_position = Point::new(player_x, player_y);
+    *player_position = Point::new(x, y);
     let mut position_components = ecs.write_storage::<Position>();
     let player_entity = ecs.fetch::<Entity>();
-    let player_pos_comp = position_components.get_mut(*player_entity);
-    if let Some(player_pos_comp) = player_pos_comp {
-        player_pos_comp.x = player_x;
-        player_pos_comp.y = player_y;
+    if let Some(player_pos_comp) = position_components.get_mut(*player_entity) {
+        player_pos_comp.x = x;
+        player_pos_comp.y = y;
     }
 
     // Mark the player's visibility as dirty
     let mut viewshed_components = ecs.write_storage::<Viewshed>();
-    let vs = viewshed_components.get_mut(*player_entity);
-    if let Some(vs) = vs {
+    if let Some(vs) = viewshed_components.get_mut(*player_entity) {
         vs.dirty = true;
     }
+}
 
-    // Store the newly minted map
-    let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
-    dungeon_master.store_map(&builder.build_data.map);
+/// Takes the stairs up (`offset` < 0) or down (`offset` > 0).
+/// An `offset` of 0 starts a new run, on the first level of the main branch.
+pub fn level_transition(ecs: &mut World, offset: i32) -> Option<Vec<Map>> {
+    let (level, from) = if offset == 0 {
+        (LevelKey::new(MAIN_BRANCH, 1), None)
+    } else {
+        let from = departure_stair(ecs, offset);
+        (destination(ecs, offset, &from), from)
+    };
 
-    mapgen_history
+    // Do we already have a map?
+    let known = ecs
+        .read_resource::<MasterDungeonMap>()
+        .get_map(&level)
+        .is_some();
+    if known {
+        transition_to_existing_map(ecs, &level, offset, from);
+        None
+    } else {
+        Some(transition_to_new_map(ecs, level, offset, from))
+    }
 }
 
-fn transition_to_existing_map(ecs: &mut World, new_depth: i32, offset: i32) {
-    let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
-    let map = dungeon_master.get_map(new_depth).unwrap();
-    let mut worldmap_resource = ecs.write_resource::<Map>();
-    let player_entity = ecs.fetch::<Entity>();
+fn transition_to_new_map(
+    ecs: &mut World,
+    level: LevelKey,
+    offset: i32,
+    from: Option<StairEnd>,
+) -> Vec<Map> {
+    let mut rng =
+        rltk::RandomNumberGenerator::seeded(ecs.fetch::<MasterDungeonMap>().level_seed(&level));
+    let mut builder = build_level(&level, None, &mut rng, 80, 50, false);
+    let mapgen_history = builder.build_data.history.clone();
+    let player_start = builder.build_data.starting_position.clone().unwrap();
+    {
+        let mut worldmap_resource = ecs.write_resource::<Map>();
+        *worldmap_resource = builder.build_data.map.clone();
+    }
 
-    // Find the down stairs and place the player
-    let w = map.width;
-    let stair_type = if offset < 0 {
-        TileType::DownStairs
+    // Spawn bad guys
+    builder.spawn_entities(ecs, &mut rng);
+
+    // Coming from above, the player arrives on the up staircase of the start
+    let map = &builder.build_data.map;
+    let start_idx = map.xy_idx(player_start.x, player_start.y);
+    let arrival_idx = if offset < 0 {
+        arrival_stair(&ecs.fetch::<MasterDungeonMap>(), map, offset).unwrap_or(start_idx)
     } else {
-        TileType::UpStairs
+        start_idx
     };
-    for (idx, tt) in map.tiles.iter().enumerate() {
-        if *tt == stair_type {
-            let mut player_position = ecs.write_resource::<Point>();
-            *player_position = Point::new(idx as i32 % w, idx as i32 / w);
-            let mut position_components = ecs.write_storage::<Position>();
-            let player_pos_comp = position_components.get_mut(*player_entity);
-            if let Some(player_pos_comp) = player_pos_comp {
-                player_pos_comp.x = idx as i32 % w;
-                player_pos_comp.y = idx as i32 / w;
-            }
-        }
+    place_player(
+        ecs,
+        arrival_idx as i32 % map.width,
+        arrival_idx as i32 / map.width,
+    );
+
+    // Store the newly minted map, with its stairs
+    let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+    dungeon_master.store_map(map);
+    for (idx, to) in builder.build_data.branch_exits.iter() {
+        dungeon_master.add_branch_stair(
+            StairEnd {
+                level: level.clone(),
+                idx: *idx,
+            },
+            to.clone(),
+        );
+    }
+    if let Some(from) = from {
+        dungeon_master.link_stairs(
+            from,
+            StairEnd {
+                level,
+                idx: arrival_idx,
+            },
+        );
     }
 
-    *worldmap_resource = map;
+    mapgen_history
+}
 
-    // Mark the player's visibility as dirty
-    let mut viewshed_components = ecs.write_storage::<Viewshed>();
-    let vs = viewshed_components.get_mut(*player_entity);
-    if let Some(vs) = vs {
-        vs.dirty = true;
+fn transition_to_existing_map(
+    ecs: &mut World,
+    level: &LevelKey,
+    offset: i32,
+    from: Option<StairEnd>,
+) {
+    let map = ecs
+        .read_resource::<MasterDungeonMap>()
+        .get_map(level)
+        .unwrap();
+
+    // Back to the staircase linked to this one, or else to a free one
+    let linked = from
+        .as_ref()
+        .and_then(|f| ecs.fetch::<MasterDungeonMap>().stair_destination(f));
+    let arrival_idx = match linked {
+        Some(to) => Some(to.idx),
+        None => {
+            let idx = arrival_stair(&ecs.fetch::<MasterDungeonMap>(), &map, offset);
+            if let (Some(from), Some(idx)) = (from, idx) {
+                ecs.write_resource::<MasterDungeonMap>().link_stairs(
+                    from,
+                    StairEnd {
+                        level: level.clone(),
+                        idx,
+                    },
+                );
+            }
+            idx
+        }
+    };
+
+    let w = map.width;
+    *ecs.write_resource::<Map>() = map;
+    match arrival_idx {
+        Some(idx) => place_player(ecs, idx as i32 % w, idx as i32 / w),
+        None => {
+            let player_pos = *ecs.fetch::<Point>();
+            place_player(ecs, player_pos.x, player_pos.y);
+        }
     }
 }
 
@@ -145,7 +357,7 @@ pub fn freeze_level_entities(ecs: &mut World) {
     let mut positions = ecs.write_storage::<Position>();
     let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
     let player_entity = ecs.fetch::<Entity>();
-    let map_depth = ecs.fetch::<Map>().depth;
+    let map = ecs.fetch::<Map>();
 
     // Find positions and make OtherLevelPosition
     let mut pos_to_delete: Vec<Entity> = Vec::new();
@@ -157,7 +369,8 @@ pub fn freeze_level_entities(ecs: &mut World) {
                     OtherLevelPosition {
                         x: pos.x,
                         y: pos.y,
-                        depth: map_depth,
+                        depth: map.depth,
+                        branch: map.branch.clone(),
                     },
                 )
                 .expect("Insert fail");
@@ -177,12 +390,12 @@ pub fn thaw_level_entities(ecs: &mut World) {
     let mut positions = ecs.write_storage::<Position>();
     let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
     let player_entity = ecs.fetch::<Entity>();
-    let map_depth = ecs.fetch::<Map>().depth;
+    let map = ecs.fetch::<Map>();
 
     // Find OtherLevelPosition
     let mut pos_to_delete: Vec<Entity> = Vec::new();
     for (entity, pos) in (&entities, &other_level_positions).join() {
-        if entity != *player_entity && pos.depth == map_depth {
+        if entity != *player_entity && pos.depth == map.depth && pos.branch == map.branch {
             positions
                 .insert(entity, Position { x: pos.x, y: pos.y })
                 .expect("Insert fail");
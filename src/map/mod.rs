@@ -12,6 +12,13 @@ pub use theme::*;
 mod dungeon;
 pub use dungeon::*;
 
+/// The branch of the levels below the town.
+pub const MAIN_BRANCH: &str = "Main";
+
+fn main_branch() -> String {
+    MAIN_BRANCH.to_string()
+}
+
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
     pub tiles: Vec<TileType>,
@@ -21,6 +28,8 @@ pub struct Map {
     pub visible_tiles: Vec<bool>,
     pub blocked: Vec<bool>,
     pub depth: i32,
+    #[serde(default = "main_branch")]
+    pub branch: String,
     pub bloodstains: HashSet<usize>,
     pub view_blocked: HashSet<usize>,
     pub name: String,
@@ -44,6 +53,7 @@ impl Map {
             blocked: vec![false; map_tile_count],
             tile_content: vec![Vec::new(); map_tile_count],
             depth: new_depth,
+            branch: main_branch(),
             bloodstains: HashSet::new(),
             view_blocked: HashSet::new(),
             name: name.to_string(),
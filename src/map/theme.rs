@@ -1,10 +1,11 @@
-use super::{Map, TileType};
+use super::{Map, TileType, MAIN_BRANCH};
 use rltk::RGB;
 
 pub fn tile_glyph(idx: usize, map: &Map) -> (u8, RGB, RGB) {
-    let (glyph, mut fg, mut bg) = match map.depth {
-        3 => get_limestone_cavern_glyph(idx, map),
-        2 => get_forest_glyph(idx, map),
+    let (glyph, mut fg, mut bg) = match (map.branch.as_str(), map.depth) {
+        (MAIN_BRANCH, 3) | ("Mines", _) => get_limestone_cavern_glyph(idx, map),
+        (MAIN_BRANCH, 2) => get_forest_glyph(idx, map),
+        ("Sewer", _) => get_sewer_glyph(idx, map),
         _ => get_tile_glyph_default(idx, map),
     };
 
@@ -204,6 +205,40 @@ fn get_limestone_cavern_glyph(idx: usize, map: &Map) -> (u8, RGB, RGB) {
     (glyph, fg, bg)
 }
 
+fn get_sewer_glyph(idx: usize, map: &Map) -> (u8, RGB, RGB) {
+    let glyph;
+    let fg;
+    let bg = RGB::from_f32(0., 0., 0.);
+
+    match map.tiles[idx] {
+        TileType::Wall => {
+            let x = idx as i32 % map.width;
+            let y = idx as i32 / map.width;
+            glyph = wall_glyph(map, x, y);
+            fg = RGB::from_f32(0.45, 0.4, 0.25);
+        }
+        TileType::Floor => {
+            glyph = rltk::to_cp437('.');
+            fg = RGB::from_f32(0.35, 0.4, 0.2);
+        }
+        TileType::ShallowWater | TileType::DeepWater => {
+            glyph = rltk::to_cp437('≈');
+            fg = RGB::from_f32(0.4, 0.5, 0.1);
+        }
+        TileType::DownStairs => {
+            glyph = rltk::to_cp437('>');
+            fg = RGB::from_f32(0., 1.0, 1.0);
+        }
+        TileType::UpStairs => {
+            glyph = rltk::to_cp437('<');
+            fg = RGB::from_f32(0., 1.0, 1.0);
+        }
+        _ => return get_tile_glyph_default(idx, map),
+    }
+
+    (glyph, fg, bg)
+}
+
 fn wall_glyph(map: &Map, x: i32, y: i32) -> u8 {
     //symbole ASCII 35 = #
     if x < 1 || x > map.width - 2 as i32 || y < 1 || y > map.height - 2 as i32 {
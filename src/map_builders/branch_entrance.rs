@@ -0,0 +1,60 @@
+use super::{BuilderMap, LevelKey, MetaMapBuilder, TileType};
+use rltk::RandomNumberGenerator;
+
+/// Places a down staircase leading to the first level of another branch,
+/// on a random floor tile reachable from the start.
+pub struct BranchEntrance {
+    to: LevelKey,
+}
+
+impl MetaMapBuilder for BranchEntrance {
+    fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap) {
+        self.build(rng, build_data);
+    }
+}
+
+impl BranchEntrance {
+    pub fn new(to: LevelKey) -> Box<BranchEntrance> {
+        Box::new(BranchEntrance { to })
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
+        let start = match &build_data.starting_position {
+            None => return,
+            Some(start) => start.clone(),
+        };
+        let start_idx = build_data.map.xy_idx(start.x, start.y);
+        build_data.map.populate_blocked();
+        let dijkstra_map = rltk::DijkstraMap::new(
+            build_data.map.width,
+            build_data.map.height,
+            &[start_idx],
+            &build_data.map,
+            (build_data.map.width * build_data.map.height) as f32,
+        );
+
+        let candidates: Vec<usize> = build_data
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, tile)| {
+                matches!(
+                    tile,
+                    TileType::Floor | TileType::Grass | TileType::Gravel | TileType::Road
+                ) && *idx != start_idx
+                    && dijkstra_map.map[*idx] != f32::MAX
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        if candidates.is_empty() {
+            build_data.rejected = Some(format!("no room for the entrance of {}", self.to.branch));
+            return;
+        }
+
+        let idx = candidates[rng.roll_dice(1, candidates.len() as i32) as usize - 1];
+        build_data.map.tiles[idx] = TileType::DownStairs;
+        build_data.branch_exits.push((idx, self.to.clone()));
+        build_data.take_snapshot();
+    }
+}
@@ -44,6 +44,20 @@ impl DLABuilder {
         })
     }
 
+    pub fn custom(
+        algorithm: DLAAlgorithm,
+        brush_size: i32,
+        symmetry: Symmetry,
+        floor_percent: f32,
+    ) -> Box<DLABuilder> {
+        Box::new(DLABuilder {
+            algorithm,
+            brush_size,
+            symmetry,
+            floor_percent,
+        })
+    }
+
     #[allow(dead_code)]
     pub fn heavy_erosion() -> Box<DLABuilder> {
         Box::new(DLABuilder {
@@ -0,0 +1,178 @@
+use super::{BuilderMap, MetaMapBuilder, TileType};
+use crate::map;
+use rltk::RandomNumberGenerator;
+
+/// Last step of every level chain: checks the finished map and repairs it when possible.
+/// * the start is walkable, with an up staircase on every level but the first one,
+/// * there is exactly one down staircase, reachable from the start (none on the last
+///   level of a branch), plus the reachable entrances of other branches,
+/// * no staircase is in a vault or on its entrance,
+/// * every spawn is on a walkable tile, and never on a staircase.
+///
+/// A map that can't be repaired is marked in `build_data.rejected`, and is built again
+/// by `build_level`.
+pub struct MapValidator {
+    up_stairs: bool,
+    down_stairs: bool,
+}
+
+impl MetaMapBuilder for MapValidator {
+    fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap) {
+        self.build(rng, build_data);
+    }
+}
+
+impl MapValidator {
+    pub fn new(up_stairs: bool, down_stairs: bool) -> Box<MapValidator> {
+        Box::new(MapValidator {
+            up_stairs,
+            down_stairs,
+        })
+    }
+
+    fn build(&mut self, _rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
+        if let Err(reason) = self.validate(build_data) {
+            build_data.rejected = Some(reason);
+        }
+        build_data.take_snapshot();
+    }
+
+    fn validate(&mut self, build_data: &mut BuilderMap) -> Result<(), String> {
+        let start = match &build_data.starting_position {
+            None => return Err("no starting position".to_string()),
+            Some(start) => start.clone(),
+        };
+        if start.x < 1
+            || start.x > build_data.map.width - 2
+            || start.y < 1
+            || start.y > build_data.map.height - 2
+        {
+            return Err(format!(
+                "starting position ({}, {}) is out of the map",
+                start.x, start.y
+            ));
+        }
+        let start_idx = build_data.map.xy_idx(start.x, start.y);
+        if !map::tile_walkable(build_data.map.tiles[start_idx]) {
+            return Err(format!(
+                "starting position ({}, {}) is not walkable",
+                start.x, start.y
+            ));
+        }
+
+        // The player arrives on an up staircase
+        if self.up_stairs {
+            if in_vault(build_data, start_idx) {
+                return Err(format!(
+                    "the up staircase ({}, {}) is in a vault",
+                    start.x, start.y
+                ));
+            }
+            build_data.map.tiles[start_idx] = TileType::UpStairs;
+        } else if build_data.map.tiles[start_idx] == TileType::DownStairs {
+            build_data.map.tiles[start_idx] = TileType::Floor;
+        }
+
+        // Distance of every tile from the start, following the BaseMap exits
+        build_data.map.populate_blocked();
+        let dijkstra_map = rltk::DijkstraMap::new(
+            build_data.map.width,
+            build_data.map.height,
+            &[start_idx],
+            &build_data.map,
+            (build_data.map.width * build_data.map.height) as f32,
+        );
+
+        for (idx, to) in build_data.branch_exits.iter() {
+            if build_data.map.tiles[*idx] != TileType::DownStairs
+                || dijkstra_map.map[*idx] == f32::MAX
+            {
+                return Err(format!("the entrance of {} is unreachable", to.branch));
+            }
+            if in_vault(build_data, *idx) {
+                return Err(format!("the entrance of {} is in a vault", to.branch));
+            }
+        }
+        self.fix_down_stairs(build_data, start_idx, &dijkstra_map.map)?;
+        self.fix_spawns(build_data, start_idx);
+        Ok(())
+    }
+
+    /// Keeps the farthest reachable down staircase out of the vaults, or digs one
+    /// on the farthest floor. The branch entrances are left alone.
+    fn fix_down_stairs(
+        &mut self,
+        build_data: &mut BuilderMap,
+        start_idx: usize,
+        distances: &[f32],
+    ) -> Result<(), String> {
+        let reachable = |idx: usize| distances[idx] != f32::MAX;
+        let stairs: Vec<usize> = build_data
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, tile)| {
+                **tile == TileType::DownStairs
+                    && !build_data.branch_exits.iter().any(|(exit, _)| exit == idx)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        let kept = if self.down_stairs {
+            stairs
+                .iter()
+                .filter(|idx| reachable(**idx) && !in_vault(build_data, **idx))
+                .max_by(|a, b| distances[**a].partial_cmp(&distances[**b]).unwrap())
+                .copied()
+        } else {
+            None
+        };
+
+        for idx in stairs.iter().filter(|idx| Some(**idx) != kept) {
+            build_data.map.tiles[*idx] = TileType::Floor;
+        }
+
+        if self.down_stairs && kept.is_none() {
+            let farthest = build_data
+                .map
+                .tiles
+                .iter()
+                .enumerate()
+                .filter(|(idx, tile)| {
+                    **tile == TileType::Floor
+                        && *idx != start_idx
+                        && reachable(*idx)
+                        && !in_vault(build_data, *idx)
+                })
+                .max_by(|a, b| distances[a.0].partial_cmp(&distances[b.0]).unwrap())
+                .map(|(idx, _)| idx);
+            match farthest {
+                None => return Err("no reachable floor for the down staircase".to_string()),
+                Some(idx) => build_data.map.tiles[idx] = TileType::DownStairs,
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the spawns that are in a wall, on a staircase or on the player.
+    fn fix_spawns(&mut self, build_data: &mut BuilderMap, start_idx: usize) {
+        let tiles = &build_data.map.tiles;
+        build_data.spawn_list.retain(|(idx, _)| {
+            *idx < tiles.len()
+                && *idx != start_idx
+                && map::tile_walkable(tiles[*idx])
+                && tiles[*idx] != TileType::DownStairs
+                && tiles[*idx] != TileType::UpStairs
+        });
+    }
+}
+
+/// True if `idx` is in one of the vaults of the map, or on its entrance.
+fn in_vault(build_data: &BuilderMap, idx: usize) -> bool {
+    let x = idx as i32 % build_data.map.width;
+    let y = idx as i32 / build_data.map.width;
+    build_data
+        .vaults
+        .iter()
+        .any(|v| x >= v.x1 && x <= v.x2 && y >= v.y1 && y <= v.y2)
+}
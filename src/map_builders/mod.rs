@@ -3,7 +3,8 @@ use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
 use super::{
-    spawner, Map, Position, Rect, TileType, SHOW_MAPGEN_VISUALIZER, SHOW_MAPGEN_VISUALIZER_COLOR,
+    spawner, LevelKey, Map, Position, Rect, TileType, SHOW_MAPGEN_VISUALIZER,
+    SHOW_MAPGEN_VISUALIZER_COLOR,
 };
 
 mod simple_map;
@@ -93,13 +94,19 @@ mod empty_map;
 use empty_map::EmptyMapBuilder;
 
 mod town;
-use town::town_builder;
 
 mod forest;
-use forest::forest_builder;
 
 mod limestone_cavern;
-use limestone_cavern::limestone_cavern_builder;
+
+mod registry;
+pub use registry::{branch_entrance, check_map_chains};
+
+mod map_validator;
+use map_validator::MapValidator;
+
+mod branch_entrance;
+use branch_entrance::BranchEntrance;
 
 pub struct BuilderMap {
     pub spawn_list: Vec<(usize, String)>,
@@ -108,13 +115,21 @@ pub struct BuilderMap {
     pub rooms: Option<Vec<Rect>>,
     pub corridors: Option<Vec<Vec<usize>>>,
     pub history: Vec<Map>,
+    /// Keeps a snapshot of every step in `history` (for the visualizer and `mapgen --history`).
+    pub record_history: bool,
+    /// Set by `MapValidator` when the map can't be repaired.
+    pub rejected: Option<String>,
+    /// Down staircases leading to the first level of another branch.
+    pub branch_exits: Vec<(usize, LevelKey)>,
+    /// Areas of the vaults placed by `PrefabBuilder`, their entrance included.
+    pub vaults: Vec<Rect>,
     pub width: i32,
     pub height: i32,
 }
 
 impl BuilderMap {
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             if SHOW_MAPGEN_VISUALIZER_COLOR {
                 // Make visible for screenshot
                 for t in self.map.visible_tiles.iter_mut() {
@@ -148,6 +163,10 @@ impl BuilderChain {
                 rooms: None,
                 corridors: None,
                 history: Vec::new(),
+                record_history: SHOW_MAPGEN_VISUALIZER,
+                rejected: None,
+                branch_exits: Vec::new(),
+                vaults: Vec::new(),
                 width,
                 height,
             },
@@ -180,12 +199,12 @@ impl BuilderChain {
         }
     }
 
-    pub fn spawn_entities(&mut self, ecs: &mut World) {
+    pub fn spawn_entities(&mut self, ecs: &mut World, rng: &mut rltk::RandomNumberGenerator) {
         for entity in self.build_data.spawn_list.iter() {
             if &entity.1 == "None" {
                 continue;
             }
-            spawner::spawn_entity(ecs, &(&entity.0, &entity.1));
+            spawner::spawn_entity(ecs, &(&entity.0, &entity.1), rng);
         }
     }
 }
@@ -198,174 +217,135 @@ pub trait MetaMapBuilder {
     fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap);
 }
 
+/// Builds the chain of `new_depth` in `branch`, as declared in `raws/map_chains.json`.
 pub fn level_builder(
+    branch: &str,
     new_depth: i32,
     rng: &mut rltk::RandomNumberGenerator,
     width: i32,
     height: i32,
 ) -> BuilderChain {
-    println!("Depth : {}", new_depth);
-    match new_depth {
-        //_ => empty_map(new_depth, rng, width, height)
-        1 => town_builder(new_depth, rng, width, height),
-        2 => forest_builder(new_depth, rng, width, height),
-        3 => limestone_cavern_builder(new_depth, rng, width, height),
-        _ => random_builder(new_depth, rng, width, height),
-    }
+    println!("Depth : {} ({})", new_depth, branch);
+    let level = LevelKey::new(branch, new_depth);
+    let chain = registry::chain_for_level(&level, rng, width, height);
+    finish_chain(chain, &level)
 }
 
-pub fn random_builder(
+/// Chain used when the chain of a level keeps being rejected: rooms joined by
+/// straight corridors always connect the start to the exit.
+const FALLBACK_CHAIN: &str = "sewer";
+
+/// Builds the level of `new_depth` in `branch` with `FALLBACK_CHAIN`, its branch
+/// entrances and stairs included.
+pub fn fallback_builder(
+    branch: &str,
     new_depth: i32,
     rng: &mut rltk::RandomNumberGenerator,
     width: i32,
     height: i32,
 ) -> BuilderChain {
-    let mut builder = BuilderChain::new(new_depth, width, height, "New Map");
-    let type_roll = rng.roll_dice(1, 2);
-    match type_roll {
-        1 => random_room_builder(rng, &mut builder),
-        _ => random_shape_builder(rng, &mut builder),
-    }
-
-    if rng.roll_dice(1, 3) == 1 {
-        builder.with(WaveformCollapseBuilder::new());
-
-        // Now set the start to a random starting area
-        let (start_x, start_y) = random_start_position(rng);
-        builder.with(AreaStartingPosition::new(start_x, start_y));
-
-        // Setup an exit and spawn mobs
-        builder.with(VoronoiSpawning::new());
-        builder.with(DistantExit::new());
-    }
-
-    if rng.roll_dice(1, 20) == 1 {
-        builder.with(PrefabBuilder::sectional(
-            prefab_builder::prefab_sections::UNDERGROUND_FORT,
-        ));
-    }
-
-    builder.with(DoorPlacement::new());
-    builder.with(PrefabBuilder::vaults());
-
-    builder
+    let level = LevelKey::new(branch, new_depth);
+    let chain = registry::named_chain(FALLBACK_CHAIN, &level, rng, width, height);
+    finish_chain(chain, &level)
 }
 
-fn random_start_position(rng: &mut rltk::RandomNumberGenerator) -> (XStart, YStart) {
-    let x;
-    let xroll = rng.roll_dice(1, 3);
-    match xroll {
-        1 => x = XStart::LEFT,
-        2 => x = XStart::CENTER,
-        _ => x = XStart::RIGHT,
-    }
-
-    let y;
-    let yroll = rng.roll_dice(1, 3);
-    match yroll {
-        1 => y = YStart::BOTTOM,
-        2 => y = YStart::CENTER,
-        _ => y = YStart::TOP,
+/// Adds the branch entrances of `level` and the `MapValidator` at the end of `chain`.
+fn finish_chain(mut chain: BuilderChain, level: &LevelKey) -> BuilderChain {
+    for to in registry::branches_entered_from(level) {
+        chain.with(BranchEntrance::new(to));
     }
-
-    (x, y)
+    chain.with(MapValidator::new(
+        registry::has_up_stairs(level),
+        registry::has_down_stairs(level),
+    ));
+    chain
 }
 
-fn random_room_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut BuilderChain) {
-    let build_roll = rng.roll_dice(1, 3);
-    match build_roll {
-        1 => builder.start_with(SimpleMapBuilder::new()),
-        2 => builder.start_with(BspDungeonBuilder::new()),
-        _ => builder.start_with(BspInteriorBuilder::new()),
-    }
-
-    // BSP Interior still makes holes in the walls
-    if build_roll != 3 {
-        // Sort by one of the 5 available algorithms
-        let sort_roll = rng.roll_dice(1, 5);
-        match sort_roll {
-            1 => builder.with(RoomSorter::new(RoomSort::LEFTMOST)),
-            2 => builder.with(RoomSorter::new(RoomSort::RIGHTMOST)),
-            3 => builder.with(RoomSorter::new(RoomSort::TOPMOST)),
-            4 => builder.with(RoomSorter::new(RoomSort::BOTTOMMOST)),
-            _ => builder.with(RoomSorter::new(RoomSort::CENTRAL)),
-        }
-
-        builder.with(RoomDrawer::new());
-
-        let corridor_roll = rng.roll_dice(1, 4);
-        match corridor_roll {
-            1 => builder.with(DoglegCorridors::new()),
-            2 => builder.with(NearestCorridors::new()),
-            3 => builder.with(StraightLineCorridors::new()),
-            _ => builder.with(BspCorridors::new()),
-        }
+/// Builds the chain called `name` in `raws/map_chains.json` instead of the one of the level,
+/// with the branch entrances and stairs of the level.
+pub fn named_builder(
+    name: &str,
+    branch: &str,
+    new_depth: i32,
+    rng: &mut rltk::RandomNumberGenerator,
+    width: i32,
+    height: i32,
+) -> BuilderChain {
+    let level = LevelKey::new(branch, new_depth);
+    let chain = registry::named_chain(name, &level, rng, width, height);
+    finish_chain(chain, &level)
+}
 
-        let cspawn_roll = rng.roll_dice(1, 2);
-        if cspawn_roll == 1 {
-            builder.with(CorridorSpawner::new());
-        }
+/// How many times a rejected map is built again before giving up.
+const MAX_MAP_ATTEMPTS: i32 = 10;
 
-        let modifier_roll = rng.roll_dice(1, 6);
-        match modifier_roll {
-            1 => builder.with(RoomExploder::new()),
-            2 => builder.with(RoomCornerRounder::new()),
-            _ => {}
+/// Builds `level` with its chain, or with the chain called `chain_name` when it is set.
+/// The map is built again while `MapValidator` rejects it, and `FALLBACK_CHAIN` is used
+/// when every attempt has been rejected. `record_history` keeps every snapshot of the build.
+pub fn build_level(
+    level: &LevelKey,
+    chain_name: Option<&str>,
+    rng: &mut rltk::RandomNumberGenerator,
+    width: i32,
+    height: i32,
+    record_history: bool,
+) -> BuilderChain {
+    let keep_history = |mut chain: BuilderChain| {
+        if record_history {
+            chain.build_data.record_history = true;
         }
-    }
-
-    let start_roll = rng.roll_dice(1, 2);
-    match start_roll {
-        1 => builder.with(RoomBasedStartingPosition::new()),
-        _ => {
-            let (start_x, start_y) = random_start_position(rng);
-            builder.with(AreaStartingPosition::new(start_x, start_y));
+        chain
+    };
+    let built = build_with_retries(rng, |rng| {
+        keep_history(match chain_name {
+            Some(name) => named_builder(name, &level.branch, level.depth, rng, width, height),
+            None => level_builder(&level.branch, level.depth, rng, width, height),
+        })
+    });
+    match built {
+        Ok(chain) => chain,
+        Err(reason) => {
+            println!("Warning : {}, using the fallback map", reason);
+            build_with_retries(rng, |rng| {
+                keep_history(fallback_builder(
+                    &level.branch,
+                    level.depth,
+                    rng,
+                    width,
+                    height,
+                ))
+            })
+            .expect("Unable to build the fallback map")
         }
     }
-
-    let exit_roll = rng.roll_dice(1, 2);
-    match exit_roll {
-        1 => builder.with(RoomBasedStairs::new()),
-        _ => builder.with(DistantExit::new()),
-    }
-
-    let spawn_roll = rng.roll_dice(1, 2);
-    match spawn_roll {
-        1 => builder.with(RoomBasedSpawner::new()),
-        _ => builder.with(VoronoiSpawning::new()),
-    }
 }
 
-fn random_shape_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut BuilderChain) {
-    let builder_roll = rng.roll_dice(1, 16);
-    match builder_roll {
-        1 => builder.start_with(CellularAutomataBuilder::new()),
-        2 => builder.start_with(DrunkardsWalkBuilder::open_area()),
-        3 => builder.start_with(DrunkardsWalkBuilder::open_halls()),
-        4 => builder.start_with(DrunkardsWalkBuilder::winding_passages()),
-        5 => builder.start_with(DrunkardsWalkBuilder::fat_passages()),
-        6 => builder.start_with(DrunkardsWalkBuilder::fearful_symmetry()),
-        7 => builder.start_with(MazeBuilder::new()),
-        8 => builder.start_with(DLABuilder::walk_inwards()),
-        9 => builder.start_with(DLABuilder::walk_outwards()),
-        10 => builder.start_with(DLABuilder::central_attractor()),
-        11 => builder.start_with(DLABuilder::insectoid()),
-        12 => builder.start_with(VoronoiCellBuilder::pythagoras()),
-        _ => builder.start_with(VoronoiCellBuilder::manhattan()),
-        //_ => builder.start_with(PrefabBuilder::constant(prefab_builder::prefab_levels::WFC_POPULATED)),
+/// Builds the chains made by `make_chain` until one passes `MapValidator`.
+/// Fails with the last reason when every attempt has been rejected.
+pub fn build_with_retries<F>(
+    rng: &mut rltk::RandomNumberGenerator,
+    mut make_chain: F,
+) -> Result<BuilderChain, String>
+where
+    F: FnMut(&mut rltk::RandomNumberGenerator) -> BuilderChain,
+{
+    let mut reason = String::new();
+    for attempt in 1..=MAX_MAP_ATTEMPTS {
+        let mut chain = make_chain(rng);
+        chain.build_map(rng);
+        match chain.build_data.rejected.take() {
+            None => return Ok(chain),
+            Some(why) => {
+                println!(
+                    "Warning : map rejected ({}), attempt {}/{}",
+                    why, attempt, MAX_MAP_ATTEMPTS
+                );
+                reason = why;
+            }
+        }
     }
-
-    // Set the start to the center and cull
-    builder.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
-    builder.with(CullUnreachable::new());
-
-    // Now set the start to a random starting area
-    //minimizes the likelihood of starting in an "orphaned" section
-    //and culling the map down to just a few tiles
-    let (start_x, start_y) = random_start_position(rng);
-    builder.with(AreaStartingPosition::new(start_x, start_y));
-
-    // Setup an exit and spawn mobs
-    builder.with(VoronoiSpawning::new());
-    builder.with(DistantExit::new());
+    Err(format!(
+        "Unable to build a valid map after {} attempts, last error : {}",
+        MAX_MAP_ATTEMPTS, reason
+    ))
 }
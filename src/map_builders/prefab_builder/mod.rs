@@ -1,4 +1,4 @@
-use super::{BuilderMap, InitialMapBuilder, MetaMapBuilder, Position, TileType};
+use super::{BuilderMap, InitialMapBuilder, MetaMapBuilder, Position, Rect, TileType};
 use rltk::RandomNumberGenerator;
 pub mod prefab_levels;
 pub mod prefab_rooms;
@@ -371,6 +371,12 @@ impl PrefabBuilder {
                         i += 1;
                     }
                 }
+                build_data.vaults.push(Rect::new(
+                    chunk_x,
+                    chunk_y,
+                    vault.width as i32 - 1,
+                    vault.height as i32 - 1,
+                ));
                 build_data.take_snapshot();
 
                 possible_vaults.remove(vault_index);
@@ -0,0 +1,856 @@
+use super::{
+    common::Symmetry,
+    dla::DLAAlgorithm,
+    drunkard::{DrunkSpawnMode, DrunkardSettings},
+    forest::YellowBrickRoad,
+    limestone_cavern::CaveDecorator,
+    prefab_builder::{prefab_levels, prefab_sections},
+    town::TownBuilder,
+    voronoi::DistanceAlgorithm,
+    *,
+};
+use crate::raws::{BranchDef, BuilderStep, ChainDef, MapChains, MAP_CHAINS};
+use crate::LevelKey;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Builds the chain declared for `level` in `raws/map_chains.json`:
+/// the fixed chain of this depth in the branch, or else a weighted random one.
+pub fn chain_for_level(
+    level: &LevelKey,
+    rng: &mut RandomNumberGenerator,
+    width: i32,
+    height: i32,
+) -> BuilderChain {
+    let chains = MAP_CHAINS.lock().unwrap();
+    let branch = find_branch(&chains, &level.branch);
+    let name = match branch.levels.iter().find(|l| l.depth == level.depth) {
+        Some(fixed) => fixed.chain.clone(),
+        None => {
+            let candidates: Vec<_> = branch
+                .random_chains
+                .iter()
+                .filter(|c| level.depth >= c.min_depth && level.depth <= c.max_depth)
+                .collect();
+            let weights: Vec<i32> = candidates.iter().map(|c| c.weight).collect();
+            match roll_weighted(rng, &weights) {
+                Some(index) => candidates[index].chain.clone(),
+                None => panic!("No map chain for {} depth {}", level.branch, level.depth),
+            }
+        }
+    };
+    build_chain(&chains, &name, level, rng, width, height)
+}
+
+pub fn named_chain(
+    name: &str,
+    level: &LevelKey,
+    rng: &mut RandomNumberGenerator,
+    width: i32,
+    height: i32,
+) -> BuilderChain {
+    let chains = MAP_CHAINS.lock().unwrap();
+    build_chain(&chains, name, level, rng, width, height)
+}
+
+fn find_branch<'a>(chains: &'a MapChains, name: &str) -> &'a BranchDef {
+    chains
+        .branches
+        .iter()
+        .find(|b| b.name == name)
+        .unwrap_or_else(|| panic!("Unknown dungeon branch [{}]", name))
+}
+
+/// The level going up from the first depth of a branch leads to.
+pub fn branch_entrance(level: &LevelKey) -> Option<LevelKey> {
+    let chains = MAP_CHAINS.lock().unwrap();
+    let branch = find_branch(&chains, &level.branch);
+    match &branch.entrance {
+        Some(entrance) if level.depth == branch.first_depth => {
+            Some(LevelKey::new(&entrance.branch, entrance.depth))
+        }
+        _ => None,
+    }
+}
+
+/// First level of every branch whose entrance is on `level`.
+pub fn branches_entered_from(level: &LevelKey) -> Vec<LevelKey> {
+    let chains = MAP_CHAINS.lock().unwrap();
+    chains
+        .branches
+        .iter()
+        .filter(|b| match &b.entrance {
+            Some(e) => e.branch == level.branch && e.depth == level.depth,
+            None => false,
+        })
+        .map(|b| LevelKey::new(&b.name, b.first_depth))
+        .collect()
+}
+
+/// Every level has an up staircase, except the very first one of the main branch.
+pub fn has_up_stairs(level: &LevelKey) -> bool {
+    let chains = MAP_CHAINS.lock().unwrap();
+    let branch = find_branch(&chains, &level.branch);
+    branch.entrance.is_some() || level.depth > branch.first_depth
+}
+
+/// The last depth of a branch has no down staircase.
+pub fn has_down_stairs(level: &LevelKey) -> bool {
+    let chains = MAP_CHAINS.lock().unwrap();
+    match find_branch(&chains, &level.branch).last_depth {
+        Some(last) => level.depth < last,
+        None => true,
+    }
+}
+
+fn build_chain(
+    chains: &MapChains,
+    name: &str,
+    level: &LevelKey,
+    rng: &mut RandomNumberGenerator,
+    width: i32,
+    height: i32,
+) -> BuilderChain {
+    let def = find_chain(chains, name).unwrap_or_else(|| panic!("Unknown map chain [{}]", name));
+    let map_name = def
+        .map_name
+        .clone()
+        .unwrap_or_else(|| "New Map".to_string());
+
+    let mut chain = BuilderChain::new(level.depth, width, height, map_name);
+    chain.build_data.map.branch = level.branch.clone();
+    add_steps(chains, &def.steps, rng, &mut chain);
+    chain
+}
+
+fn find_chain<'a>(chains: &'a MapChains, name: &str) -> Option<&'a ChainDef> {
+    chains.chains.iter().find(|c| c.name == name)
+}
+
+fn add_steps(
+    chains: &MapChains,
+    steps: &[BuilderStep],
+    rng: &mut RandomNumberGenerator,
+    chain: &mut BuilderChain,
+) {
+    for step in steps.iter() {
+        if let Some(name) = &step.builder {
+            add_builder(name, step.params.as_ref(), rng, chain);
+        } else if let Some(name) = &step.chain {
+            let def =
+                find_chain(chains, name).unwrap_or_else(|| panic!("Unknown map chain [{}]", name));
+            add_steps(chains, &def.steps, rng, chain);
+        } else if let Some(options) = &step.one_of {
+            let weights: Vec<i32> = options.iter().map(|o| o.weight).collect();
+            if let Some(index) = roll_weighted(rng, &weights) {
+                add_steps(chains, &options[index].steps, rng, chain);
+            }
+        } else if let Some(sub_steps) = &step.steps {
+            let chance = step.chance.unwrap_or(1);
+            if chance <= 1 || rng.roll_dice(1, chance) == 1 {
+                add_steps(chains, sub_steps, rng, chain);
+            }
+        }
+    }
+}
+
+/// The first builder of a chain starts it, the others modify the map.
+/// `check_map_chains` has already refused the chains where this could panic.
+fn add_builder(
+    name: &str,
+    values: Option<&HashMap<String, Value>>,
+    rng: &mut RandomNumberGenerator,
+    chain: &mut BuilderChain,
+) {
+    let (_, initial, meta) =
+        find_builder(name).unwrap_or_else(|| panic!("Unknown map builder [{}]", name));
+    let mut params = Params::new(values);
+    if chain.starter.is_none() {
+        match initial {
+            Some(initial) => chain.start_with(initial(&mut params)),
+            None => panic!("Map builder [{}] can't start a chain", name),
+        }
+    } else {
+        match meta {
+            Some(meta) => chain.with(meta(&mut params, rng)),
+            None => panic!("Map builder [{}] can only start a chain", name),
+        }
+    }
+}
+
+type InitialFn = fn(&mut Params) -> Box<dyn InitialMapBuilder>;
+type MetaFn = fn(&mut Params, &mut RandomNumberGenerator) -> Box<dyn MetaMapBuilder>;
+
+/// A builder name, how it starts a chain and/or how it modifies the map.
+type BuilderDef = (&'static str, Option<InitialFn>, Option<MetaFn>);
+
+/// Every builder the chains can name.
+const BUILDERS: &[BuilderDef] = &[
+    ("SimpleMapBuilder", Some(|_| SimpleMapBuilder::new()), None),
+    (
+        "BspDungeonBuilder",
+        Some(|_| BspDungeonBuilder::new()),
+        None,
+    ),
+    (
+        "BspInteriorBuilder",
+        Some(|_| BspInteriorBuilder::new()),
+        None,
+    ),
+    (
+        "CellularAutomataBuilder",
+        Some(|_| CellularAutomataBuilder::new()),
+        Some(|_, _| CellularAutomataBuilder::new()),
+    ),
+    (
+        "DrunkardsWalkBuilder",
+        Some(|p| drunkard(p)),
+        Some(|p, _| drunkard(p)),
+    ),
+    ("MazeBuilder", Some(|_| MazeBuilder::new()), None),
+    ("DLABuilder", Some(|p| dla(p)), Some(|p, _| dla(p))),
+    ("VoronoiCellBuilder", Some(|p| voronoi(p)), None),
+    ("PrefabBuilder", Some(|p| prefab(p)), Some(|p, _| prefab(p))),
+    ("EmptyMapBuilder", Some(|_| EmptyMapBuilder::new()), None),
+    ("TownBuilder", Some(|_| TownBuilder::new()), None),
+    (
+        "WaveformCollapseBuilder",
+        None,
+        Some(|_, _| WaveformCollapseBuilder::new()),
+    ),
+    (
+        "RoomBasedSpawner",
+        None,
+        Some(|_, _| RoomBasedSpawner::new()),
+    ),
+    (
+        "RoomBasedStartingPosition",
+        None,
+        Some(|_, _| RoomBasedStartingPosition::new()),
+    ),
+    ("RoomBasedStairs", None, Some(|_, _| RoomBasedStairs::new())),
+    (
+        "AreaStartingPosition",
+        None,
+        Some(|p, rng| area_starting_position(p, rng)),
+    ),
+    ("VoronoiSpawning", None, Some(|_, _| VoronoiSpawning::new())),
+    ("CullUnreachable", None, Some(|_, _| CullUnreachable::new())),
+    ("DistantExit", None, Some(|_, _| DistantExit::new())),
+    ("RoomExploder", None, Some(|_, _| RoomExploder::new())),
+    (
+        "RoomCornerRounder",
+        None,
+        Some(|_, _| RoomCornerRounder::new()),
+    ),
+    ("RoomSorter", None, Some(|p, _| room_sorter(p))),
+    ("RoomDrawer", None, Some(|_, _| RoomDrawer::new())),
+    ("DoglegCorridors", None, Some(|_, _| DoglegCorridors::new())),
+    ("BspCorridors", None, Some(|_, _| BspCorridors::new())),
+    (
+        "NearestCorridors",
+        None,
+        Some(|_, _| NearestCorridors::new()),
+    ),
+    (
+        "StraightLineCorridors",
+        None,
+        Some(|_, _| StraightLineCorridors::new()),
+    ),
+    ("CorridorSpawner", None, Some(|_, _| CorridorSpawner::new())),
+    ("DoorPlacement", None, Some(|_, _| DoorPlacement::new())),
+    ("YellowBrickRoad", None, Some(|_, _| YellowBrickRoad::new())),
+    ("CaveDecorator", None, Some(|_, _| CaveDecorator::new())),
+];
+
+fn find_builder(name: &str) -> Option<&'static BuilderDef> {
+    BUILDERS.iter().find(|b| b.0 == name)
+}
+
+/// Picks an index with a probability proportional to its weight.
+fn roll_weighted(rng: &mut RandomNumberGenerator, weights: &[i32]) -> Option<usize> {
+    let total: i32 = weights.iter().filter(|w| **w > 0).sum();
+    if total <= 0 {
+        return None;
+    }
+    let mut roll = rng.roll_dice(1, total) - 1;
+    for (index, weight) in weights.iter().enumerate() {
+        if *weight <= 0 {
+            continue;
+        }
+        if roll < *weight {
+            return Some(index);
+        }
+        roll -= weight;
+    }
+    None
+}
+
+/// Checks `raws/map_chains.json` once it is loaded, so that a mistake shows at startup
+/// instead of when its level is generated: branches, chains, steps, builder names and params.
+/// Returns every problem found. The params that no builder reads are only warned about.
+pub fn check_map_chains(chains: &MapChains) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let mut chain_names = HashSet::new();
+    for def in chains.chains.iter() {
+        if !chain_names.insert(def.name.as_str()) {
+            problems.push(format!("Duplicate map chain [{}]", def.name));
+        }
+        check_steps(chains, &def.name, &def.steps, &mut problems);
+        if reaches(chains, &def.name, &def.name, &mut Vec::new()) {
+            problems.push(format!("Map chain [{}] includes itself", def.name));
+        }
+    }
+
+    // Les chaînes de départ : celles des branches, celle de secours, et celles
+    // qu'aucune autre n'inclut (mapgen --chain)
+    let mut roots = vec![FALLBACK_CHAIN.to_string()];
+    let mut branch_names = HashSet::new();
+    for branch in chains.branches.iter() {
+        if !branch_names.insert(branch.name.as_str()) {
+            problems.push(format!("Duplicate dungeon branch [{}]", branch.name));
+        }
+        check_branch(chains, branch, &mut problems);
+        roots.extend(branch.levels.iter().map(|l| l.chain.clone()));
+        roots.extend(branch.random_chains.iter().map(|c| c.chain.clone()));
+    }
+    for def in chains.chains.iter() {
+        let included = chains
+            .chains
+            .iter()
+            .any(|c| c.name != def.name && sub_chains(&c.steps).contains(&def.name.as_str()));
+        if !included {
+            roots.push(def.name.clone());
+        }
+    }
+    roots.sort();
+    roots.dedup();
+
+    for name in roots.iter() {
+        match find_chain(chains, name) {
+            None => problems.push(format!("Unknown map chain [{}]", name)),
+            Some(def) => {
+                let mut stack = vec![name.clone()];
+                let started = check_starts(
+                    chains,
+                    name,
+                    &def.steps,
+                    Started::No,
+                    &mut stack,
+                    &mut problems,
+                );
+                if started != Started::Yes {
+                    problems.push(format!("Map chain [{}] may have no starting builder", name));
+                }
+            }
+        }
+    }
+
+    // Une chaîne incluse par plusieurs racines est vérifiée plusieurs fois
+    let mut seen = HashSet::new();
+    problems.retain(|p| seen.insert(p.clone()));
+    problems
+}
+
+/// The chains named by the `chain` steps of `steps`, one_of and nested steps included.
+fn sub_chains(steps: &[BuilderStep]) -> Vec<&str> {
+    let mut names = Vec::new();
+    for step in steps.iter() {
+        if let Some(name) = &step.chain {
+            names.push(name.as_str());
+        }
+        for option in step.one_of.iter().flatten() {
+            names.extend(sub_chains(&option.steps));
+        }
+        if let Some(sub_steps) = &step.steps {
+            names.extend(sub_chains(sub_steps));
+        }
+    }
+    names
+}
+
+/// Whether the chain `name` ends up including `target`, through its sub-chains.
+fn reaches(chains: &MapChains, name: &str, target: &str, seen: &mut Vec<String>) -> bool {
+    if seen.iter().any(|s| s == name) {
+        return false;
+    }
+    seen.push(name.to_string());
+    match find_chain(chains, name) {
+        None => false,
+        Some(def) => sub_chains(&def.steps)
+            .into_iter()
+            .any(|sub| sub == target || reaches(chains, sub, target, seen)),
+    }
+}
+
+/// The entrance of `branch` leads somewhere and each of its depths has a chain.
+fn check_branch(chains: &MapChains, branch: &BranchDef, problems: &mut Vec<String>) {
+    if let Some(entrance) = &branch.entrance {
+        match chains.branches.iter().find(|b| b.name == entrance.branch) {
+            None => problems.push(format!(
+                "Branch [{}] is entered from an unknown branch [{}]",
+                branch.name, entrance.branch
+            )),
+            Some(from) => {
+                if entrance.depth < from.first_depth
+                    || from.last_depth.is_some_and(|last| entrance.depth > last)
+                {
+                    problems.push(format!(
+                        "Branch [{}] is entered from depth {}, which [{}] doesn't have",
+                        branch.name, entrance.depth, from.name
+                    ));
+                }
+            }
+        }
+    }
+
+    for random in branch.random_chains.iter() {
+        if random.min_depth > random.max_depth {
+            problems.push(format!(
+                "Branch [{}] : random chain [{}] has min_depth above max_depth",
+                branch.name, random.chain
+            ));
+        }
+    }
+
+    // Sans dernier niveau, on vérifie jusqu'à la plus grande profondeur déclarée
+    let last_depth = branch.last_depth.unwrap_or_else(|| {
+        let fixed = branch.levels.iter().map(|l| l.depth);
+        let random = branch.random_chains.iter().map(|c| c.max_depth);
+        fixed.chain(random).max().unwrap_or(branch.first_depth)
+    });
+    for depth in branch.first_depth..=last_depth {
+        let fixed = branch.levels.iter().any(|l| l.depth == depth);
+        let random = branch
+            .random_chains
+            .iter()
+            .any(|c| c.weight > 0 && depth >= c.min_depth && depth <= c.max_depth);
+        if !fixed && !random {
+            problems.push(format!(
+                "Branch [{}] has no map chain for depth {}",
+                branch.name, depth
+            ));
+        }
+    }
+}
+
+/// Each step is one of the four kinds, with known builders and valid params.
+fn check_steps(
+    chains: &MapChains,
+    chain_name: &str,
+    steps: &[BuilderStep],
+    problems: &mut Vec<String>,
+) {
+    for step in steps.iter() {
+        let kinds = [
+            step.builder.is_some(),
+            step.chain.is_some(),
+            step.one_of.is_some(),
+            step.steps.is_some(),
+        ];
+        if kinds.iter().filter(|k| **k).count() != 1 {
+            problems.push(format!(
+                "Map chain [{}] : a step needs exactly one of builder, chain, one_of or steps",
+                chain_name
+            ));
+            continue;
+        }
+        if step.params.is_some() && step.builder.is_none() {
+            problems.push(format!(
+                "Map chain [{}] : params only go with a builder",
+                chain_name
+            ));
+        }
+        if step.chance.is_some() && step.steps.is_none() {
+            problems.push(format!(
+                "Map chain [{}] : chance only goes with steps",
+                chain_name
+            ));
+        }
+
+        if let Some(name) = &step.builder {
+            check_builder(chain_name, name, step.params.as_ref(), problems);
+        } else if let Some(name) = &step.chain {
+            if find_chain(chains, name).is_none() {
+                problems.push(format!(
+                    "Map chain [{}] : unknown map chain [{}]",
+                    chain_name, name
+                ));
+            }
+        } else if let Some(options) = &step.one_of {
+            if !options.iter().any(|o| o.weight > 0) {
+                problems.push(format!(
+                    "Map chain [{}] : one_of has no positive weight",
+                    chain_name
+                ));
+            }
+            for option in options.iter() {
+                check_steps(chains, chain_name, &option.steps, problems);
+            }
+        } else if let Some(sub_steps) = &step.steps {
+            check_steps(chains, chain_name, sub_steps, problems);
+        }
+    }
+}
+
+/// Makes the builder once with its params, to find out the values it refuses
+/// and the keys it never reads.
+fn check_builder(
+    chain_name: &str,
+    name: &str,
+    values: Option<&HashMap<String, Value>>,
+    problems: &mut Vec<String>,
+) {
+    let (_, initial, meta) = match find_builder(name) {
+        Some(builder) => builder,
+        None => {
+            problems.push(format!(
+                "Map chain [{}] : unknown map builder [{}]",
+                chain_name, name
+            ));
+            return;
+        }
+    };
+    let mut params = Params::new(values);
+    match (initial, meta) {
+        (Some(initial), _) => {
+            initial(&mut params);
+        }
+        (None, Some(meta)) => {
+            meta(&mut params, &mut RandomNumberGenerator::seeded(0));
+        }
+        (None, None) => {}
+    }
+    for problem in params.problems.iter() {
+        problems.push(format!("Map chain [{}] : {} {}", chain_name, name, problem));
+    }
+    for key in params.unused() {
+        println!(
+            "Warning: map chain [{}] : {} ignores param [{}]",
+            chain_name, name, key
+        );
+    }
+}
+
+/// Whether the chain already has its starting builder at some point of its steps.
+#[derive(Clone, Copy, PartialEq)]
+enum Started {
+    No,
+    Maybe,
+    Yes,
+}
+
+fn either(a: Started, b: Started) -> Started {
+    if a == b {
+        a
+    } else {
+        Started::Maybe
+    }
+}
+
+/// Follows every path through `steps` (sub-chains, one_of, chance) to check that
+/// the first builder of each can start a chain and the next ones can modify it.
+fn check_starts(
+    chains: &MapChains,
+    chain_name: &str,
+    steps: &[BuilderStep],
+    mut started: Started,
+    stack: &mut Vec<String>,
+    problems: &mut Vec<String>,
+) -> Started {
+    for step in steps.iter() {
+        if let Some(name) = &step.builder {
+            if let Some((_, initial, meta)) = find_builder(name) {
+                if started != Started::Yes && initial.is_none() {
+                    problems.push(format!(
+                        "Map chain [{}] : map builder [{}] can't start a chain",
+                        chain_name, name
+                    ));
+                }
+                if started != Started::No && meta.is_none() {
+                    problems.push(format!(
+                        "Map chain [{}] : map builder [{}] can only start a chain",
+                        chain_name, name
+                    ));
+                }
+            }
+            started = Started::Yes;
+        } else if let Some(name) = &step.chain {
+            // Les boucles sont déjà signalées par `reaches`
+            if stack.contains(name) {
+                continue;
+            }
+            if let Some(def) = find_chain(chains, name) {
+                stack.push(name.clone());
+                started = check_starts(chains, name, &def.steps, started, stack, problems);
+                stack.pop();
+            }
+        } else if let Some(options) = &step.one_of {
+            let mut after = None;
+            for option in options.iter().filter(|o| o.weight > 0) {
+                let s = check_starts(chains, chain_name, &option.steps, started, stack, problems);
+                after = Some(after.map_or(s, |a| either(a, s)));
+            }
+            started = after.unwrap_or(started);
+        } else if let Some(sub_steps) = &step.steps {
+            let s = check_starts(chains, chain_name, sub_steps, started, stack, problems);
+            started = match step.chance {
+                Some(chance) if chance > 1 => either(started, s),
+                _ => s,
+            };
+        }
+    }
+    started
+}
+
+/// The `params` of a step, with a default value for each missing or invalid key.
+/// The invalid values and the keys read are kept for `check_map_chains`.
+struct Params<'a> {
+    values: Option<&'a HashMap<String, Value>>,
+    read: Vec<String>,
+    problems: Vec<String>,
+}
+
+impl<'a> Params<'a> {
+    fn new(values: Option<&'a HashMap<String, Value>>) -> Params<'a> {
+        Params {
+            values,
+            read: Vec::new(),
+            problems: Vec::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<&'a Value> {
+        self.read.push(key.to_string());
+        self.values.and_then(|p| p.get(key))
+    }
+
+    fn has(&mut self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// The keys given to the builder that it never read.
+    fn unused(&self) -> Vec<&String> {
+        let mut keys: Vec<&String> = match self.values {
+            None => Vec::new(),
+            Some(values) => values.keys().filter(|k| !self.read.contains(k)).collect(),
+        };
+        keys.sort();
+        keys
+    }
+
+    fn text(&mut self, key: &str) -> Option<String> {
+        let value = self.get(key)?;
+        match value.as_str() {
+            Some(text) => Some(text.to_lowercase()),
+            None => {
+                self.problems
+                    .push(format!("param [{}] should be a string", key));
+                None
+            }
+        }
+    }
+
+    /// One of `options`, the first one when the key is missing or unknown.
+    fn choice(&mut self, key: &str, options: &[&str]) -> String {
+        match self.text(key) {
+            Some(value) if options.contains(&value.as_str()) => value,
+            Some(value) => {
+                self.problems.push(format!(
+                    "param [{}] : unknown value [{}], expected one of {:?}",
+                    key, value, options
+                ));
+                options[0].to_string()
+            }
+            None => options[0].to_string(),
+        }
+    }
+
+    fn int(&mut self, key: &str, default: i32) -> i32 {
+        match self.get(key).map(|value| value.as_i64()) {
+            None => default,
+            Some(Some(value)) => value as i32,
+            Some(None) => {
+                self.problems
+                    .push(format!("param [{}] should be an integer", key));
+                default
+            }
+        }
+    }
+
+    fn float(&mut self, key: &str, default: f32) -> f32 {
+        match self.get(key).map(|value| value.as_f64()) {
+            None => default,
+            Some(Some(value)) => value as f32,
+            Some(None) => {
+                self.problems
+                    .push(format!("param [{}] should be a number", key));
+                default
+            }
+        }
+    }
+
+    fn symmetry(&mut self) -> Symmetry {
+        match self
+            .choice("symmetry", &["none", "horizontal", "vertical", "both"])
+            .as_str()
+        {
+            "horizontal" => Symmetry::Horizontal,
+            "vertical" => Symmetry::Vertical,
+            "both" => Symmetry::Both,
+            _ => Symmetry::None,
+        }
+    }
+}
+
+fn drunkard(params: &mut Params) -> Box<DrunkardsWalkBuilder> {
+    if params.has("preset") {
+        let presets = [
+            "open_area",
+            "open_halls",
+            "winding_passages",
+            "fat_passages",
+            "fearful_symmetry",
+        ];
+        return match params.choice("preset", &presets).as_str() {
+            "open_halls" => DrunkardsWalkBuilder::open_halls(),
+            "winding_passages" => DrunkardsWalkBuilder::winding_passages(),
+            "fat_passages" => DrunkardsWalkBuilder::fat_passages(),
+            "fearful_symmetry" => DrunkardsWalkBuilder::fearful_symmetry(),
+            _ => DrunkardsWalkBuilder::open_area(),
+        };
+    }
+
+    let spawn_mode = match params
+        .choice("spawn_mode", &["random", "starting_point"])
+        .as_str()
+    {
+        "starting_point" => DrunkSpawnMode::StartingPoint,
+        _ => DrunkSpawnMode::Random,
+    };
+    Box::new(DrunkardsWalkBuilder::new(DrunkardSettings {
+        spawn_mode,
+        drunken_lifetime: params.int("drunken_lifetime", 400),
+        floor_percent: params.float("floor_percent", 0.5),
+        brush_size: params.int("brush_size", 1),
+        symmetry: params.symmetry(),
+    }))
+}
+
+fn dla(params: &mut Params) -> Box<DLABuilder> {
+    if params.has("preset") {
+        let presets = [
+            "heavy_erosion",
+            "walk_inwards",
+            "walk_outwards",
+            "central_attractor",
+            "insectoid",
+        ];
+        return match params.choice("preset", &presets).as_str() {
+            "walk_inwards" => DLABuilder::walk_inwards(),
+            "walk_outwards" => DLABuilder::walk_outwards(),
+            "central_attractor" => DLABuilder::central_attractor(),
+            "insectoid" => DLABuilder::insectoid(),
+            _ => DLABuilder::heavy_erosion(),
+        };
+    }
+
+    let algorithm = match params
+        .choice(
+            "algorithm",
+            &["walk_inwards", "walk_outwards", "central_attractor"],
+        )
+        .as_str()
+    {
+        "walk_outwards" => DLAAlgorithm::WalkOutwards,
+        "central_attractor" => DLAAlgorithm::CentralAttractor,
+        _ => DLAAlgorithm::WalkInwards,
+    };
+    DLABuilder::custom(
+        algorithm,
+        params.int("brush_size", 2),
+        params.symmetry(),
+        params.float("floor_percent", 0.25),
+    )
+}
+
+fn voronoi(params: &mut Params) -> Box<VoronoiCellBuilder> {
+    let distance = match params
+        .choice("distance", &["pythagoras", "manhattan", "chebyshev"])
+        .as_str()
+    {
+        "manhattan" => DistanceAlgorithm::Manhattan,
+        "chebyshev" => DistanceAlgorithm::Chebyshev,
+        _ => DistanceAlgorithm::Pythagoras,
+    };
+    VoronoiCellBuilder::custom(params.int("seeds", 64) as usize, distance)
+}
+
+fn prefab(params: &mut Params) -> Box<PrefabBuilder> {
+    match params
+        .choice("mode", &["vaults", "sectional", "constant"])
+        .as_str()
+    {
+        "sectional" => {
+            params.choice("section", &["underground_fort"]);
+            PrefabBuilder::sectional(prefab_sections::UNDERGROUND_FORT)
+        }
+        "constant" => {
+            params.choice("level", &["wfc_populated"]);
+            PrefabBuilder::constant(prefab_levels::WFC_POPULATED)
+        }
+        _ => PrefabBuilder::vaults(),
+    }
+}
+
+fn area_starting_position(
+    params: &mut Params,
+    rng: &mut RandomNumberGenerator,
+) -> Box<AreaStartingPosition> {
+    // "random" is rolled when the chain is assembled, like the other random steps
+    let x = match params
+        .choice("x", &["center", "left", "right", "random"])
+        .as_str()
+    {
+        "left" => XStart::LEFT,
+        "right" => XStart::RIGHT,
+        "random" => match rng.roll_dice(1, 3) {
+            1 => XStart::LEFT,
+            2 => XStart::CENTER,
+            _ => XStart::RIGHT,
+        },
+        _ => XStart::CENTER,
+    };
+    let y = match params
+        .choice("y", &["center", "top", "bottom", "random"])
+        .as_str()
+    {
+        "top" => YStart::TOP,
+        "bottom" => YStart::BOTTOM,
+        "random" => match rng.roll_dice(1, 3) {
+            1 => YStart::BOTTOM,
+            2 => YStart::CENTER,
+            _ => YStart::TOP,
+        },
+        _ => YStart::CENTER,
+    };
+    AreaStartingPosition::new(x, y)
+}
+
+fn room_sorter(params: &mut Params) -> Box<RoomSorter> {
+    let sort = match params
+        .choice(
+            "sort",
+            &["leftmost", "rightmost", "topmost", "bottommost", "central"],
+        )
+        .as_str()
+    {
+        "rightmost" => RoomSort::RIGHTMOST,
+        "topmost" => RoomSort::TOPMOST,
+        "bottommost" => RoomSort::BOTTOMMOST,
+        "central" => RoomSort::CENTRAL,
+        _ => RoomSort::LEFTMOST,
+    };
+    RoomSorter::new(sort)
+}
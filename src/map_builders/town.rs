@@ -1,19 +1,5 @@
-use super::{
-    AreaStartingPosition, BuilderChain, BuilderMap, DistantExit, InitialMapBuilder, Position,
-    TileType,
-};
-use std::collections::HashSet;
-
-pub fn town_builder(
-    new_depth: i32,
-    _rng: &mut rltk::RandomNumberGenerator,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "The Town of Creation");
-    chain.start_with(TownBuilder::new());
-    chain
-}
+use super::{BuilderMap, InitialMapBuilder, Position, TileType};
+use std::collections::BTreeSet;
 
 enum BuildingTag {
     Pub,
@@ -113,8 +99,8 @@ impl TownBuilder {
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
         build_data: &mut BuilderMap,
-    ) -> (HashSet<usize>, i32) {
-        let mut available_building_tiles: HashSet<usize> = HashSet::new();
+    ) -> (BTreeSet<usize>, i32) {
+        let mut available_building_tiles: BTreeSet<usize> = BTreeSet::new();
         let wall_gap_y = rng.roll_dice(1, build_data.height - 9) + 5;
         for y in 1..build_data.height - 2 {
             if !(y > wall_gap_y - 4 && y < wall_gap_y + 4) {
@@ -155,7 +141,7 @@ impl TownBuilder {
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
         build_data: &mut BuilderMap,
-        available_building_tiles: &mut HashSet<usize>,
+        available_building_tiles: &mut BTreeSet<usize>,
     ) -> Vec<(i32, i32, i32, i32)> {
         let mut buildings: Vec<(i32, i32, i32, i32)> = Vec::new();
         let mut n_buildings = 0;
@@ -533,7 +519,7 @@ impl TownBuilder {
         &mut self,
         build_data: &mut BuilderMap,
         rng: &mut rltk::RandomNumberGenerator,
-        available_building_tiles: &mut HashSet<usize>,
+        available_building_tiles: &mut BTreeSet<usize>,
     ) {
         //tiles we know won't be inside of a building, because we removed them when we placed buildings
         for idx in available_building_tiles.iter() {
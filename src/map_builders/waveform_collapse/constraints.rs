@@ -55,12 +55,11 @@ pub fn build_patterns(
     }
 
     //Dedupe
-    //de-duplicate by adding the patterns into a HashMap (which can only store one of each entry)
-    //and then reading it back out again.
+    //de-duplicate by keeping the first copy of each pattern, so the order (and the seeded map) stays the same
     if dedupe {
         println!("Pre de-duplication, there are {} patterns.", patterns.len());
-        let set: HashSet<Vec<TileType>> = patterns.drain(..).collect();
-        patterns.extend(set.into_iter());
+        let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+        patterns.retain(|pattern| seen.insert(pattern.clone()));
         println!("There are {} patterns.", patterns.len());
     }
 
@@ -30,6 +30,7 @@ impl WaveformCollapseBuilder {
         const CHUNK_SIZE: i32 = 8;
         build_data.take_snapshot();
 
+        let branch = build_data.map.branch.clone();
         let patterns = build_patterns(&build_data.map, CHUNK_SIZE, true, true);
         let constraints = patterns_to_constraints(patterns, CHUNK_SIZE);
         self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
@@ -39,6 +40,7 @@ impl WaveformCollapseBuilder {
             build_data.map.height,
             &build_data.map.name,
         );
+        build_data.map.branch = branch;
         loop {
             let mut solver = Solver::new(constraints.clone(), CHUNK_SIZE, &build_data.map);
             while !solver.iteration(&mut build_data.map, rng) {
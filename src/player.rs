@@ -1,9 +1,268 @@
 use super::raws::Reaction;
 use super::*;
 use rltk::{Point, Rltk, VirtualKeyCode};
+use serde::{Deserialize, Serialize};
+use specs::saveload::{MarkedBuilder, SimpleMarker};
 use std::cmp::{max, min};
 
+/// Une action du joueur, indépendante du clavier et de la fenêtre.
+/// Les objets sont désignés par leur position dans le menu correspondant,
+/// et DropItem ne lâche que `amount` objets d'une pile quand il est donné.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum PlayerCommand {
+    Move { dx: i32, dy: i32 },
+    PickUp,
+    SkipTurn,
+    UseItem { index: usize, target: Option<Point> },
+    DropItem { index: usize, amount: Option<i32> },
+    RemoveItem { index: usize },
+    Fire { target: Option<Point> },
+    CastSpell { index: usize, target: Option<Point> },
+    RevealMap,
+    TeleportToExit,
+    LevelUp { choice: LevelUpChoice },
+}
+
+/// Applies a command for the player and returns the next RunState.
+/// The RNG is reseeded for the turn first, and the command is recorded for the
+/// replay unless it still waits for a target.
+pub fn apply_command(gs: &mut State, command: PlayerCommand) -> RunState {
+    system_particle::delete_particles(&mut gs.ecs);
+    replay::reseed_rng(&mut gs.ecs);
+    let result = run_command(gs, command);
+    match result {
+        RunState::ShowTargeting { .. }
+        | RunState::ShowFireTargeting { .. }
+        | RunState::ShowSpellTargeting { .. } => {}
+        _ => gs.ecs.fetch_mut::<replay::Replay>().record(command),
+    }
+    result
+}
+
+fn run_command(gs: &mut State, command: PlayerCommand) -> RunState {
+    match command {
+        PlayerCommand::Move { dx, dy } => try_move_player(dx, dy, &mut gs.ecs),
+        PlayerCommand::PickUp => {
+            get_item(&mut gs.ecs);
+            RunState::Ticking
+        }
+        PlayerCommand::SkipTurn => skip_turn(&mut gs.ecs),
+        PlayerCommand::UseItem { index, target } => {
+            let items = gui::menu_items(&gs.ecs, &MenuType::Inventory);
+            let item = match items.get(index) {
+                None => return RunState::AwaitingInput,
+                Some(item) => *item,
+            };
+            if target.is_none() {
+                if let Some(ranged) = gs.ecs.read_storage::<Ranged>().get(item) {
+                    return RunState::ShowTargeting {
+                        range: ranged.range,
+                        item,
+                    };
+                }
+            }
+            let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
+            intent
+                .insert(*gs.ecs.fetch::<Entity>(), WantsToUseItem { item, target })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::DropItem { index, amount } => {
+            let items = gui::menu_items(&gs.ecs, &MenuType::Inventory);
+            let item = match items.get(index) {
+                None => return RunState::AwaitingInput,
+                Some(item) => *item,
+            };
+            let item = match amount {
+                None => item,
+                Some(amount) if amount < 1 => return RunState::AwaitingInput,
+                Some(amount) => split_stack(&mut gs.ecs, item, amount),
+            };
+            let mut intent = gs.ecs.write_storage::<WantsToDropItem>();
+            intent
+                .insert(*gs.ecs.fetch::<Entity>(), WantsToDropItem { item })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::RemoveItem { index } => {
+            let items = gui::menu_items(&gs.ecs, &MenuType::RemoveItem);
+            let item = match items.get(index) {
+                None => return RunState::AwaitingInput,
+                Some(item) => *item,
+            };
+            let mut intent = gs.ecs.write_storage::<WantsToRemoveItem>();
+            intent
+                .insert(*gs.ecs.fetch::<Entity>(), WantsToRemoveItem { item })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::Fire { target } => fire(&mut gs.ecs, target),
+        PlayerCommand::CastSpell { index, target } => {
+            let spells = gui::menu_items(&gs.ecs, &MenuType::Spells);
+            let spell = match spells.get(index) {
+                None => return RunState::AwaitingInput,
+                Some(spell) => *spell,
+            };
+            let player_entity = *gs.ecs.fetch::<Entity>();
+            let cost = gs
+                .ecs
+                .read_storage::<SpellTemplate>()
+                .get(spell)
+                .unwrap()
+                .mana_cost;
+            let mana = gs
+                .ecs
+                .read_storage::<Pools>()
+                .get(player_entity)
+                .unwrap()
+                .mana
+                .current;
+            if cost > mana {
+                let name = gs
+                    .ecs
+                    .read_storage::<Name>()
+                    .get(spell)
+                    .unwrap()
+                    .name
+                    .clone();
+                gs.ecs
+                    .fetch_mut::<GameLog>()
+                    .entries
+                    .insert(0, format!("You don't have enough mana to cast {}.", name));
+                return RunState::AwaitingInput;
+            }
+            if target.is_none() {
+                if let Some(ranged) = gs.ecs.read_storage::<Ranged>().get(spell) {
+                    return RunState::ShowSpellTargeting {
+                        range: ranged.range,
+                        spell,
+                    };
+                }
+            }
+            let mut intent = gs.ecs.write_storage::<WantsToCastSpell>();
+            intent
+                .insert(player_entity, WantsToCastSpell { spell, target })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        PlayerCommand::RevealMap => RunState::MagicMapReveal { row: 0 },
+        PlayerCommand::TeleportToExit => {
+            // Le dernier niveau d'une branche n'a pas de sortie
+            if !gs.ecs.fetch::<Map>().tiles.contains(&TileType::DownStairs) {
+                gs.ecs
+                    .fetch_mut::<GameLog>()
+                    .entries
+                    .insert(0, "There is no way down from here.".to_string());
+                return RunState::AwaitingInput;
+            }
+            gs.goto_level(1);
+            gs.mapgen_next_state = Some(RunState::PreRun);
+            RunState::MapGeneration
+        }
+        PlayerCommand::LevelUp { choice } => level_up(&mut gs.ecs, choice),
+    }
+}
+
+/// True while the player has level-up points left to spend.
+pub fn pending_level_up(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<LevelUpPoints>()
+        .get(*player_entity)
+        .is_some_and(|points| points.attribute_points > 0 || points.skill_points > 0)
+}
+
+/// Spends a level-up point on `choice`, then recomputes the pools it changes.
+/// Doesn't take a turn.
+fn level_up(ecs: &mut World, choice: LevelUpChoice) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let mut level_up_points = ecs.write_storage::<LevelUpPoints>();
+    let mut attributes = ecs.write_storage::<Attributes>();
+    let mut skills = ecs.write_storage::<Skills>();
+    let mut pools = ecs.write_storage::<Pools>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+
+    let points = match level_up_points.get_mut(player_entity) {
+        None => return RunState::AwaitingInput,
+        Some(points) => points,
+    };
+    let player_attributes = attributes.get_mut(player_entity).unwrap();
+    let player_skills = skills.get_mut(player_entity).unwrap();
+    if choice.apply(points, player_attributes, player_skills) {
+        recompute_player_pools(pools.get_mut(player_entity).unwrap(), player_attributes);
+        log.entries.insert(
+            0,
+            format!(
+                "Your {} rises to {}.",
+                choice.name(),
+                choice.value(player_attributes, player_skills)
+            ),
+        );
+    }
+
+    if points.attribute_points > 0 || points.skill_points > 0 {
+        RunState::LevelUp
+    } else {
+        level_up_points.remove(player_entity);
+        RunState::AwaitingInput
+    }
+}
+
+/// Bumping into someone friendly who lifts curses (the temple priest) frees the
+/// cursed equipment of the player. Returns false if there was nothing to do.
+fn ask_to_lift_curses(delta_x: i32, delta_y: i32, ecs: &mut World) -> bool {
+    let player_entity = *ecs.fetch::<Entity>();
+    let helper = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        let (x, y) = (player_pos.x + delta_x, player_pos.y + delta_y);
+        if x < 1 || x > map.width - 1 || y < 1 || y > map.height - 1 {
+            return false;
+        }
+        let removers = ecs.read_storage::<RemovesCurse>();
+        let factions = ecs.read_storage::<Faction>();
+        let player_faction = factions
+            .get(player_entity)
+            .map_or("Player", |f| f.name.as_str());
+        map.tile_content[map.xy_idx(x, y)]
+            .iter()
+            .find(|e| {
+                removers.get(**e).is_some()
+                    && factions.get(**e).is_none_or(|faction| {
+                        crate::raws::faction_reaction(
+                            &faction.name,
+                            player_faction,
+                            &crate::raws::RAWS.lock().unwrap(),
+                        ) != Reaction::Attack
+                    })
+            })
+            .copied()
+    };
+    let helper = match helper {
+        None => return false,
+        Some(helper) => helper,
+    };
+
+    let freed = remove_curses(ecs, player_entity);
+    if freed.is_empty() {
+        return false;
+    }
+    let helper_name = ecs
+        .read_storage::<Name>()
+        .get(helper)
+        .map_or("Someone".to_string(), |n| n.name.clone());
+    for name in freed {
+        log(
+            ecs,
+            format!("The {} lifts the curse on {}.", helper_name, name),
+        );
+    }
+    true
+}
+
 pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    if ask_to_lift_curses(delta_x, delta_y, ecs) {
+        return RunState::Ticking;
+    }
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.read_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
@@ -24,6 +283,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     for (entity, _player, pos, viewshed) in
         (&entities, &players, &mut positions, &mut viewsheds).join()
     {
+        let player_faction = factions.get(entity).map_or("Player", |f| f.name.as_str());
         if pos.x + delta_x < 1
             || pos.x + delta_x > map.width - 1
             || pos.y + delta_y < 1
@@ -39,7 +299,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
                 if let Some(faction) = factions.get(*potential_target) {
                     let reaction = crate::raws::faction_reaction(
                         &faction.name,
-                        "Player",
+                        player_faction,
                         &crate::raws::RAWS.lock().unwrap(),
                     );
                     if reaction != Reaction::Attack {
@@ -184,10 +444,228 @@ fn get_item(ecs: &mut World) {
     }
 }
 
+fn log(ecs: &World, message: String) {
+    ecs.fetch_mut::<GameLog>().entries.insert(0, message);
+}
+
+/// Shoots at `target` with the ranged weapon, asking for the target first if needed.
+/// One ammunition is used by the shot, and sometimes falls on the target's tile.
+fn fire(ecs: &mut World, target: Option<Point>) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+
+    // L'arme à distance équipée
+    let weapon = {
+        let equipped = ecs.read_storage::<Equipped>();
+        let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+        let names = ecs.read_storage::<Name>();
+        (&equipped, &ranged_weapons, &names)
+            .join()
+            .find(|(e, _, _)| e.owner == player_entity && e.slot == EquipmentSlot::Ranged)
+            .map(|(_, weapon, name)| (weapon.clone(), name.name.clone()))
+    };
+    let (weapon, weapon_name) = match weapon {
+        None => {
+            log(ecs, "You have no ranged weapon equipped.".to_string());
+            return RunState::AwaitingInput;
+        }
+        Some(weapon) => weapon,
+    };
+
+    // Les munitions du sac qui lui conviennent
+    let ammo_stack = match &weapon.ammo {
+        None => None,
+        Some(ammo_type) => {
+            let entities = ecs.entities();
+            let backpack = ecs.read_storage::<InBackpack>();
+            let ammunition = ecs.read_storage::<Ammunition>();
+            let stack = (&entities, &backpack, &ammunition)
+                .join()
+                .find(|(_, b, a)| b.owner == player_entity && &a.ammo_type == ammo_type)
+                .map(|(e, _, _)| e);
+            if stack.is_none() {
+                log(
+                    ecs,
+                    format!("You have nothing to shoot with your {}.", weapon_name),
+                );
+                return RunState::AwaitingInput;
+            }
+            stack
+        }
+    };
+
+    let target = match target {
+        None => {
+            return RunState::ShowFireTargeting {
+                range: weapon.range,
+            }
+        }
+        Some(target) => target,
+    };
+
+    // Une créature visible, à portée, sur la case visée
+    let victim = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        let viewsheds = ecs.read_storage::<Viewshed>();
+        let pools = ecs.read_storage::<Pools>();
+        let in_sight = viewsheds
+            .get(player_entity)
+            .is_some_and(|vs| vs.visible_tiles.contains(&target));
+        let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, target);
+        if !in_sight || distance > weapon.range as f32 {
+            None
+        } else {
+            map.tile_content[map.xy_idx(target.x, target.y)]
+                .iter()
+                .find(|e| **e != player_entity && pools.get(**e).is_some())
+                .copied()
+        }
+    };
+    let victim = match victim {
+        None => {
+            log(ecs, "There is nothing to shoot there.".to_string());
+            return RunState::AwaitingInput;
+        }
+        Some(victim) => victim,
+    };
+    ecs.write_storage::<WantsToShoot>()
+        .insert(player_entity, WantsToShoot { target: victim })
+        .expect("Unable to insert intent");
+
+    if let Some(stack) = ammo_stack {
+        use_ammunition(ecs, stack, target);
+    }
+    RunState::Ticking
+}
+
+/// Takes one ammunition from `stack`; it may land on `target` to be picked up again.
+fn use_ammunition(ecs: &mut World, stack: Entity, target: Point) {
+    let (name, recover_chance) = {
+        let names = ecs.read_storage::<Name>();
+        let ammunition = ecs.read_storage::<Ammunition>();
+        (
+            names.get(stack).unwrap().name.clone(),
+            ammunition.get(stack).unwrap().recover_chance,
+        )
+    };
+
+    let empty = {
+        let mut quantities = ecs.write_storage::<Quantity>();
+        match quantities.get_mut(stack) {
+            Some(qty) => {
+                qty.amount -= 1;
+                qty.amount < 1
+            }
+            None => true,
+        }
+    };
+    if empty {
+        ecs.delete_entity(stack).expect("Unable to delete");
+    }
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(player_entity, EquipmentChanged {})
+        .expect("Unable to insert");
+
+    let recovered = ecs
+        .write_resource::<rltk::RandomNumberGenerator>()
+        .roll_dice(1, 100)
+        <= recover_chance;
+    if recovered {
+        let dropped = crate::raws::spawn_named_entity(
+            &crate::raws::RAWS.lock().unwrap(),
+            ecs,
+            &name,
+            crate::raws::SpawnType::AtPosition {
+                x: target.x,
+                y: target.y,
+            },
+        );
+        if let Some(dropped) = dropped {
+            ecs.write_storage::<Quantity>()
+                .insert(dropped, Quantity { amount: 1 })
+                .expect("Unable to insert");
+        }
+    }
+}
+
+/// Copies the components listed from one entity to another.
+macro_rules! copy_components {
+    ($ecs:expr, $from:expr, $to:expr, $( $type:ty),*) => {
+        $(
+        let component = $ecs.read_storage::<$type>().get($from).cloned();
+        if let Some(component) = component {
+            $ecs.write_storage::<$type>()
+                .insert($to, component)
+                .expect("Unable to insert");
+        }
+        )*
+    };
+}
+
+/// Takes `amount` items off `stack` into a new stack in the backpack, and returns it.
+/// Returns `stack` itself when all of it is taken.
+fn split_stack(ecs: &mut World, stack: Entity, amount: i32) -> Entity {
+    let held = ecs
+        .read_storage::<Quantity>()
+        .get(stack)
+        .map_or(1, |q| q.amount);
+    if amount >= held {
+        return stack;
+    }
+
+    // La nouvelle pile garde tout ce que portait l'ancienne
+    let player_entity = *ecs.fetch::<Entity>();
+    let split = ecs
+        .create_entity()
+        .marked::<SimpleMarker<SerializeMe>>()
+        .with(InBackpack {
+            owner: player_entity,
+        })
+        .with(Quantity { amount })
+        .build();
+    copy_components!(
+        ecs,
+        stack,
+        split,
+        Renderable,
+        Name,
+        Item,
+        Consumable,
+        ProvidesHealing,
+        ProvidesFood,
+        InflictsDamage,
+        InflictsStatus,
+        AreaOfEffect,
+        Ranged,
+        Confusion,
+        MagicMapper,
+        RemovesCurse,
+        TeachesSpell,
+        Equippable,
+        MeleeWeapon,
+        RangedWeapon,
+        Ammunition,
+        Wearable,
+        Resistances,
+        AttributeModifiers,
+        SkillModifiers,
+        Rarity,
+        Cursed
+    );
+    if let Some(qty) = ecs.write_storage::<Quantity>().get_mut(stack) {
+        qty.amount -= amount;
+    }
+    split
+}
+
 fn skip_turn(ecs: &mut World) -> RunState {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
     let factions = ecs.read_storage::<Faction>();
+    let player_faction = factions
+        .get(*player_entity)
+        .map_or("Player", |f| f.name.as_str());
 
     let worldmap_resource = ecs.fetch::<Map>();
 
@@ -202,7 +680,7 @@ fn skip_turn(ecs: &mut World) -> RunState {
                 Some(faction) => {
                     let reaction = crate::raws::faction_reaction(
                         &faction.name,
-                        "Player",
+                        player_faction,
                         &crate::raws::RAWS.lock().unwrap(),
                     );
                     if reaction == Reaction::Attack {
@@ -251,45 +729,60 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
             return use_consumable_hotkey(gs, key - 1);
         }
     }
+    if ctx.control {
+        let key: Option<usize> = match ctx.key {
+            Some(VirtualKeyCode::Key1) => Some(1),
+            Some(VirtualKeyCode::Key2) => Some(2),
+            Some(VirtualKeyCode::Key3) => Some(3),
+            Some(VirtualKeyCode::Key4) => Some(4),
+            Some(VirtualKeyCode::Key5) => Some(5),
+            Some(VirtualKeyCode::Key6) => Some(6),
+            Some(VirtualKeyCode::Key7) => Some(7),
+            Some(VirtualKeyCode::Key8) => Some(8),
+            Some(VirtualKeyCode::Key9) => Some(9),
+            _ => None,
+        };
+        if let Some(key) = key {
+            return apply_command(
+                gs,
+                PlayerCommand::CastSpell {
+                    index: key - 1,
+                    target: None,
+                },
+            );
+        }
+    }
 
-    match ctx.key {
+    let command = match ctx.key {
         None => return RunState::AwaitingInput, //Rien ne se passe
         Some(key) => match key {
             VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => {
-                return try_move_player(-1, 0, &mut gs.ecs)
+                PlayerCommand::Move { dx: -1, dy: 0 }
             }
 
             VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => {
-                return try_move_player(1, 0, &mut gs.ecs)
+                PlayerCommand::Move { dx: 1, dy: 0 }
             }
 
             VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
-                return try_move_player(0, -1, &mut gs.ecs)
+                PlayerCommand::Move { dx: 0, dy: -1 }
             }
 
             VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
-                return try_move_player(0, 1, &mut gs.ecs)
+                PlayerCommand::Move { dx: 0, dy: 1 }
             }
 
             // Diagonals
-            VirtualKeyCode::Numpad9 | VirtualKeyCode::Y => {
-                return try_move_player(1, -1, &mut gs.ecs)
-            }
+            VirtualKeyCode::Numpad9 | VirtualKeyCode::Y => PlayerCommand::Move { dx: 1, dy: -1 },
 
-            VirtualKeyCode::Numpad7 | VirtualKeyCode::U => {
-                return try_move_player(-1, -1, &mut gs.ecs)
-            }
+            VirtualKeyCode::Numpad7 | VirtualKeyCode::U => PlayerCommand::Move { dx: -1, dy: -1 },
 
-            VirtualKeyCode::Numpad3 | VirtualKeyCode::N => {
-                return try_move_player(1, 1, &mut gs.ecs)
-            }
+            VirtualKeyCode::Numpad3 | VirtualKeyCode::N => PlayerCommand::Move { dx: 1, dy: 1 },
 
-            VirtualKeyCode::Numpad1 | VirtualKeyCode::B => {
-                return try_move_player(-1, 1, &mut gs.ecs)
-            }
+            VirtualKeyCode::Numpad1 | VirtualKeyCode::B => PlayerCommand::Move { dx: -1, dy: 1 },
 
             //Item interaction
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
+            VirtualKeyCode::G => PlayerCommand::PickUp,
 
             VirtualKeyCode::I => return RunState::ShowInventory,
 
@@ -297,59 +790,49 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
 
             VirtualKeyCode::R => return RunState::ShowRemoveItem,
 
+            VirtualKeyCode::F => PlayerCommand::Fire { target: None },
+
+            VirtualKeyCode::Z => return RunState::ShowCastSpell,
+
             VirtualKeyCode::Escape => return RunState::SaveGame,
 
             // Cheating!
             VirtualKeyCode::Period => return RunState::ShowCheatMenu,
 
-            VirtualKeyCode::M => {
-                return RunState::MagicMapReveal { row: 0 };
-            }
+            VirtualKeyCode::M => PlayerCommand::RevealMap,
 
             // Skip Turn
-            VirtualKeyCode::Numpad5 => return skip_turn(&mut gs.ecs),
-            VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
+            VirtualKeyCode::Numpad5 => PlayerCommand::SkipTurn,
+            VirtualKeyCode::Space => PlayerCommand::SkipTurn,
 
             _ => return RunState::AwaitingInput,
         },
-    }
-    RunState::Ticking
+    };
+    apply_command(gs, command)
 }
 
 fn use_consumable_hotkey(gs: &mut State, key: i32) -> RunState {
-    let consumables = gs.ecs.read_storage::<Consumable>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let entities = gs.ecs.entities();
-    let mut carried_consumables = Vec::new();
-    for (entity, carried_by, _consumable) in (&entities, &backpack, &consumables).join() {
-        if carried_by.owner == *player_entity {
-            carried_consumables.push(entity);
-        }
-    }
+    let carried_consumables: Vec<Entity> = {
+        let consumables = gs.ecs.read_storage::<Consumable>();
+        gui::menu_items(&gs.ecs, &MenuType::Inventory)
+            .into_iter()
+            .filter(|item| consumables.get(*item).is_some())
+            .collect()
+    };
 
     if (key as usize) < carried_consumables.len() {
-        if let Some(ranged) = gs
-            .ecs
-            .read_storage::<Ranged>()
-            .get(carried_consumables[key as usize])
-        {
-            return RunState::ShowTargeting {
-                range: ranged.range,
-                item: carried_consumables[key as usize],
-            };
-        }
-        let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
-        intent
-            .insert(
-                *player_entity,
-                WantsToUseItem {
-                    item: carried_consumables[key as usize],
-                    target: None,
-                },
-            )
-            .expect("Unable to insert intent");
-        return RunState::Ticking;
+        let index = gui::menu_index(
+            &gs.ecs,
+            &MenuType::Inventory,
+            carried_consumables[key as usize],
+        );
+        return apply_command(
+            gs,
+            PlayerCommand::UseItem {
+                index,
+                target: None,
+            },
+        );
     }
     RunState::Ticking
 }
@@ -0,0 +1,36 @@
+use super::AttributeModifiersDef;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A prefix ("Keen Longsword") or a suffix ("Longsword of the Bear") that can
+/// be rolled on a weapon or a wearable when it spawns.
+#[derive(Deserialize, Debug)]
+pub struct Affix {
+    pub name: String,
+    /// "prefix" or "suffix".
+    pub kind: String,
+    /// "weapon", "armor" or "any".
+    pub applies_to: String,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub weight: i32,
+    pub hit_bonus: Option<i32>,
+    pub damage_bonus: Option<i32>,
+    pub armor_class: Option<f32>,
+    pub attributes: Option<AttributeModifiersDef>,
+    pub skills: Option<HashMap<String, i32>>,
+}
+
+/// How many affixes and how much enchantment an item gets, and the color of its name.
+#[derive(Deserialize, Debug)]
+pub struct RarityTier {
+    pub name: String,
+    pub color: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub add_map_depth_to_weight: Option<bool>,
+    pub prefixes: i32,
+    pub suffixes: i32,
+    /// "+N": N is added to hit and damage of a weapon, or to the armor class.
+    pub max_enchantment: i32,
+}
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A past the player picks at character creation.
+#[derive(Deserialize, Debug)]
+pub struct Background {
+    pub name: String,
+    pub description: String,
+    /// Replaces the starting score of 1 in these skills.
+    pub skills: HashMap<String, i32>,
+    pub equipped: Vec<String>,
+    pub carried: Vec<String>,
+    pub spells: Option<Vec<String>>,
+    /// How the other factions see the player, from the faction table.
+    pub faction: String,
+}
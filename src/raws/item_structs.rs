@@ -5,9 +5,26 @@ use std::collections::HashMap;
 pub struct Item {
     pub name: String,
     pub renderable: Option<Renderable>,
+    pub weight_lbs: Option<f32>,
     pub consumable: Option<Consumable>,
     pub weapon: Option<Weapon>,
     pub wearable: Option<Wearable>,
+    pub ammunition: Option<AmmunitionDef>,
+    /// Added to the attributes of whoever equips it.
+    pub attributes: Option<AttributeModifiersDef>,
+    /// Skill name -> added to that skill while equipped.
+    pub skills: Option<HashMap<String, i32>>,
+    /// "scroll" or "potion": shown under a random name until identified.
+    pub unidentified: Option<String>,
+    pub curse: Option<CurseDef>,
+}
+
+/// Chance in 100 that the item spawns cursed, and what the curse takes off
+/// its hit and damage, or its armor class.
+#[derive(Deserialize, Debug)]
+pub struct CurseDef {
+    pub chance: i32,
+    pub penalty: Option<i32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -25,14 +42,57 @@ pub struct Consumable {
 
 #[derive(Deserialize, Debug)]
 pub struct Weapon {
+    /// "melee", or the number of tiles a ranged weapon can shoot.
     pub range: String,
     pub attribute: String,
     pub base_damage: String,
     pub hit_bonus: i32,
+    /// Ammunition type used by a ranged weapon, none for a thrown weapon.
+    pub ammo: Option<String>,
+    /// Bludgeoning if missing.
+    pub damage_type: Option<String>,
+    pub on_hit: Option<Vec<OnHitDef>>,
+}
+
+/// An on-hit effect of a weapon or a natural attack : a status name ("poison"...)
+/// or "confusion", "knockback", "disarm", "life_drain", with its value if it has one.
+#[derive(Deserialize, Debug)]
+pub struct OnHitDef {
+    pub effect: String,
+    pub chance: i32,
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AmmunitionDef {
+    pub ammo_type: String,
+    /// Size of the stack when it spawns.
+    pub quantity: i32,
+    pub recover_chance: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AttributeModifiersDef {
+    pub might: Option<i32>,
+    pub fitness: Option<i32>,
+    pub quickness: Option<i32>,
+    pub intelligence: Option<i32>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Wearable {
     pub armor_class: f32,
     pub slot: String,
+    pub resistances: Option<ResistancesDef>,
+}
+
+/// Damage type names, for mobs and wearables.
+#[derive(Deserialize, Debug)]
+pub struct ResistancesDef {
+    #[serde(default)]
+    pub resist: Vec<String>,
+    #[serde(default)]
+    pub vulnerable: Vec<String>,
+    #[serde(default)]
+    pub immune: Vec<String>,
 }
@@ -0,0 +1,78 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct MapChains {
+    pub branches: Vec<BranchDef>,
+    pub chains: Vec<ChainDef>,
+}
+
+/// A branch of the dungeon, with its own levels. Only the main branch has no `entrance`.
+#[derive(Deserialize, Debug)]
+pub struct BranchDef {
+    pub name: String,
+    /// Level holding the staircase that goes down to `first_depth`.
+    pub entrance: Option<BranchEntranceDef>,
+    #[serde(default = "first_depth")]
+    pub first_depth: i32,
+    /// No down staircase on this depth.
+    pub last_depth: Option<i32>,
+    #[serde(default)]
+    pub levels: Vec<LevelChain>,
+    #[serde(default)]
+    pub random_chains: Vec<RandomChain>,
+}
+
+fn first_depth() -> i32 {
+    1
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BranchEntranceDef {
+    pub branch: String,
+    pub depth: i32,
+}
+
+/// Fixed chain for one depth.
+#[derive(Deserialize, Debug)]
+pub struct LevelChain {
+    pub depth: i32,
+    pub chain: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChainDef {
+    pub name: String,
+    pub map_name: Option<String>,
+    pub steps: Vec<BuilderStep>,
+}
+
+/// Chain picked at random (by weight) for the depths without a fixed chain.
+#[derive(Deserialize, Debug)]
+pub struct RandomChain {
+    pub chain: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: i32,
+}
+
+/// One step of a chain. Exactly one of `builder`, `chain`, `one_of` or `steps` is expected:
+/// * `builder` + `params` : a builder from the registry,
+/// * `chain` : every step of another chain,
+/// * `one_of` : one of the weighted step lists,
+/// * `steps` : a step list, played one time out of `chance` if it is set.
+#[derive(Deserialize, Debug)]
+pub struct BuilderStep {
+    pub builder: Option<String>,
+    pub params: Option<HashMap<String, serde_json::Value>>,
+    pub chain: Option<String>,
+    pub one_of: Option<Vec<WeightedSteps>>,
+    pub chance: Option<i32>,
+    pub steps: Option<Vec<BuilderStep>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WeightedSteps {
+    pub weight: i32,
+    pub steps: Vec<BuilderStep>,
+}
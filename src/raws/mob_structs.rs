@@ -1,4 +1,4 @@
-use super::Renderable;
+use super::{OnHitDef, Renderable, ResistancesDef};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -20,6 +20,15 @@ pub struct Mob {
     pub loot_table: Option<String>,
     pub light: Option<MobLight>,
     pub faction: Option<String>,
+    pub chase: Option<MobChase>,
+    pub resistances: Option<ResistancesDef>,
+    /// Names of the spells it can cast, if it has the mana.
+    pub spells: Option<Vec<String>>,
+    /// Energy gained per tick, 10 if missing.
+    pub speed: Option<i32>,
+    /// Bumping into it lifts the curses of the player's equipment.
+    #[serde(default)]
+    pub removes_curses: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -41,6 +50,10 @@ pub struct NaturalAttack {
     pub name: String,
     pub hit_bonus: i32,
     pub damage: String,
+    pub range: Option<i32>,
+    /// Bludgeoning if missing.
+    pub damage_type: Option<String>,
+    pub on_hit: Option<Vec<OnHitDef>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -48,3 +61,10 @@ pub struct MobLight {
     pub range: i32,
     pub color: String,
 }
+
+/// Defaults to a 15 steps path and 5 turns of search when missing.
+#[derive(Deserialize, Debug)]
+pub struct MobChase {
+    pub max_path: Option<i32>,
+    pub search_turns: Option<i32>,
+}
@@ -1,25 +1,34 @@
 use serde::Deserialize;
 
+mod affix_structs;
+mod background_structs;
 mod faction_struct;
 mod item_structs;
 mod loot_structs;
+mod map_chain_structs;
 mod mob_structs;
 mod prop_structs;
 mod rawmaster;
 mod spawn_table_structs;
+mod spell_structs;
 
+pub use affix_structs::*;
+pub use background_structs::*;
 pub use faction_struct::*;
 pub use item_structs::*;
 pub use loot_structs::*;
+pub use map_chain_structs::*;
 pub use mob_structs::*;
 pub use prop_structs::*;
 pub use rawmaster::*;
 pub use spawn_table_structs::*;
+pub use spell_structs::*;
 
 use std::sync::Mutex;
 
 lazy_static! {
     pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::empty());
+    pub static ref MAP_CHAINS: Mutex<MapChains> = Mutex::new(MapChains::default());
 }
 
 #[derive(Deserialize, Debug)]
@@ -30,9 +39,18 @@ pub struct Raws {
     pub spawn_table: Vec<SpawnTableEntry>,
     pub loot_tables: Vec<LootTable>,
     pub faction_table: Vec<FactionInfo>,
+    pub spells: Vec<Spell>,
+    pub backgrounds: Vec<Background>,
+    pub affixes: Vec<Affix>,
+    pub rarities: Vec<RarityTier>,
 }
 
 rltk::embedded_resource!(RAW_FILE, "../../raws/spawns.json");
+rltk::embedded_resource!(MAP_CHAINS_FILE, "../../raws/map_chains.json");
+
+/// Map chains are read from this file when it exists, so that they can be tuned
+/// without recompiling. Otherwise the embedded copy is used.
+const MAP_CHAINS_PATH: &str = "./raws/map_chains.json";
 
 pub fn load_raws() {
     rltk::link_resource!(RAW_FILE, "../../raws/spawns.json");
@@ -48,4 +66,40 @@ pub fn load_raws() {
     let decoder: Raws = serde_json::from_str(&raw_string).expect("Unable to parse JSON");
 
     RAWS.lock().unwrap().load(decoder);
+
+    load_map_chains();
+}
+
+fn load_map_chains() {
+    rltk::link_resource!(MAP_CHAINS_FILE, "../../raws/map_chains.json");
+
+    let chains_string = match std::fs::read_to_string(MAP_CHAINS_PATH) {
+        Ok(chains_string) => chains_string,
+        Err(_) => {
+            let chains_data = rltk::embedding::EMBED
+                .lock()
+                .unwrap()
+                .get_resource("../../raws/map_chains.json".to_string())
+                .unwrap();
+            std::str::from_utf8(chains_data)
+                .expect("Unable to convert to a valid UTF-8 string.")
+                .to_string()
+        }
+    };
+    let decoder: MapChains =
+        serde_json::from_str(&chains_string).expect("Unable to parse the map chains JSON");
+
+    // Toutes les erreurs d'un coup, plutôt qu'un panic à la génération du niveau
+    let problems = crate::map_builders::check_map_chains(&decoder);
+    for problem in problems.iter() {
+        println!("Error: {}", problem);
+    }
+    if !problems.is_empty() {
+        panic!(
+            "The map chains have {} problem(s), see above",
+            problems.len()
+        );
+    }
+
+    *MAP_CHAINS.lock().unwrap() = decoder;
 }
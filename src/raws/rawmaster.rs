@@ -1,8 +1,9 @@
-use super::Raws;
+use super::{Affix, Background, Raws};
+use crate::ai::{DEFAULT_CHASE_PATH, DEFAULT_SEARCH_TURNS};
 use crate::components::*;
 use crate::random_table::RandomTable;
 use crate::raws::faction_struct::Reaction;
-use crate::{attr_bonus, mana_at_level, npc_hp};
+use crate::{attr_bonus, mana_at_level, npc_hp, ACTION_COST, MAIN_BRANCH, NORMAL_SPEED};
 use regex::Regex;
 use specs::prelude::*;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
@@ -42,6 +43,7 @@ pub struct RawMaster {
     mob_index: HashMap<String, usize>,
     prop_index: HashMap<String, usize>,
     loot_index: HashMap<String, usize>,
+    spell_index: HashMap<String, usize>,
     faction_index: HashMap<String, HashMap<String, Reaction>>,
 }
 
@@ -55,11 +57,16 @@ impl RawMaster {
                 spawn_table: Vec::new(),
                 loot_tables: Vec::new(),
                 faction_table: Vec::new(),
+                spells: Vec::new(),
+                backgrounds: Vec::new(),
+                affixes: Vec::new(),
+                rarities: Vec::new(),
             },
             item_index: HashMap::new(),
             mob_index: HashMap::new(),
             prop_index: HashMap::new(),
             loot_index: HashMap::new(),
+            spell_index: HashMap::new(),
             faction_index: HashMap::new(),
         }
     }
@@ -72,6 +79,14 @@ impl RawMaster {
             if used_names.contains(&item.name) {
                 println!("WARNING -  duplicate item name in raws [{}]", item.name);
             }
+            if let Some(kind) = &item.unidentified {
+                if kind != "scroll" && kind != "potion" {
+                    println!(
+                        "WARNING - {} has an unknown unidentified kind {}",
+                        item.name, kind
+                    );
+                }
+            }
             self.item_index.insert(item.name.clone(), i);
             used_names.insert(item.name.clone());
         }
@@ -103,6 +118,21 @@ impl RawMaster {
             self.loot_index.insert(loot.name.clone(), i);
         }
 
+        self.spell_index = HashMap::new();
+        for (i, spell) in self.raws.spells.iter().enumerate() {
+            if self.spell_index.contains_key(&spell.name) {
+                println!("WARNING -  duplicate spell name in raws [{}]", spell.name);
+            }
+            self.spell_index.insert(spell.name.clone(), i);
+        }
+        for mob in self.raws.mobs.iter() {
+            for spell in mob.spells.iter().flatten() {
+                if !self.spell_index.contains_key(spell) {
+                    println!("WARNING - {} knows unspecified spell {}", mob.name, spell);
+                }
+            }
+        }
+
         for faction in self.raws.faction_table.iter() {
             let mut reactions: HashMap<String, Reaction> = HashMap::new();
             for other in faction.responses.iter() {
@@ -117,6 +147,62 @@ impl RawMaster {
             }
             self.faction_index.insert(faction.name.clone(), reactions);
         }
+
+        for background in self.raws.backgrounds.iter() {
+            for item in background.equipped.iter().chain(background.carried.iter()) {
+                if !self.item_index.contains_key(item) {
+                    println!(
+                        "WARNING - {} background starts with unspecified item {}",
+                        background.name, item
+                    );
+                }
+            }
+            for spell in background.spells.iter().flatten() {
+                if !self.spell_index.contains_key(spell) {
+                    println!(
+                        "WARNING - {} background knows unspecified spell {}",
+                        background.name, spell
+                    );
+                }
+            }
+            if !self.faction_index.contains_key(&background.faction) {
+                println!(
+                    "WARNING - {} background belongs to unspecified faction {}",
+                    background.name, background.faction
+                );
+            }
+        }
+
+        for affix in self.raws.affixes.iter() {
+            if affix.kind != "prefix" && affix.kind != "suffix" {
+                println!(
+                    "WARNING - affix {} has an unknown kind {}",
+                    affix.name, affix.kind
+                );
+            }
+            if !["weapon", "armor", "any"].contains(&affix.applies_to.as_str()) {
+                println!(
+                    "WARNING - affix {} applies to unknown items {}",
+                    affix.name, affix.applies_to
+                );
+            }
+            for skill in affix.skills.iter().flat_map(|skills| skills.keys()) {
+                if string_to_skill(skill).is_none() {
+                    println!(
+                        "WARNING - affix {} has an unknown skill {}",
+                        affix.name, skill
+                    );
+                }
+            }
+        }
+        for tier in self.raws.rarities.iter() {
+            if rltk::RGB::from_hex(&tier.color).is_err() {
+                println!(
+                    "WARNING - rarity {} has an invalid color {}",
+                    tier.name, tier.color
+                );
+            }
+        }
     }
 }
 
@@ -126,14 +212,23 @@ fn find_slot_for_equippable_item(tag: &str, raws: &RawMaster) -> EquipmentSlot {
     }
     let item_index = raws.item_index[tag];
     let item = &raws.raws.items[item_index];
-    if let Some(_wpn) = &item.weapon {
-        return EquipmentSlot::Melee;
+    if let Some(wpn) = &item.weapon {
+        return weapon_slot(wpn);
     } else if let Some(wearable) = &item.wearable {
         return string_to_slot(&wearable.slot);
     }
     panic!("Trying to equip {}, but it has no slot tag.", tag);
 }
 
+/// Weapons with a range in tiles go in the ranged slot.
+fn weapon_slot(weapon: &super::Weapon) -> EquipmentSlot {
+    if weapon.range.parse::<i32>().is_ok() {
+        EquipmentSlot::Ranged
+    } else {
+        EquipmentSlot::Melee
+    }
+}
+
 fn spawn_position<'a>(
     pos: SpawnType,
     new_entity: EntityBuilder<'a>,
@@ -172,7 +267,10 @@ pub fn string_to_slot(slot: &str) -> EquipmentSlot {
         "Legs" => EquipmentSlot::Legs,
         "Feet" => EquipmentSlot::Feet,
         "Hands" => EquipmentSlot::Hands,
+        "Neck" => EquipmentSlot::Neck,
+        "Ring" => EquipmentSlot::Ring,
         "Melee" => EquipmentSlot::Melee,
+        "Ranged" => EquipmentSlot::Ranged,
         _ => {
             println!("Warning: unknown equipment slot type [{}])", slot);
             EquipmentSlot::Melee
@@ -180,6 +278,178 @@ pub fn string_to_slot(slot: &str) -> EquipmentSlot {
     }
 }
 
+pub fn string_to_skill(name: &str) -> Option<Skill> {
+    match name {
+        "Melee" => Some(Skill::Melee),
+        "Defense" => Some(Skill::Defense),
+        "Magic" => Some(Skill::Magic),
+        _ => {
+            println!("Unknown skill referenced: [{}]", name);
+            None
+        }
+    }
+}
+
+pub fn string_to_damage_type(name: &str) -> DamageType {
+    match name {
+        "Slashing" => DamageType::Slashing,
+        "Piercing" => DamageType::Piercing,
+        "Bludgeoning" => DamageType::Bludgeoning,
+        "Fire" => DamageType::Fire,
+        "Cold" => DamageType::Cold,
+        "Poison" => DamageType::Poison,
+        "Magic" => DamageType::Magic,
+        _ => {
+            println!("Warning: unknown damage type [{}]", name);
+            DamageType::Bludgeoning
+        }
+    }
+}
+
+fn damage_type_or_default(name: Option<&String>) -> DamageType {
+    name.map_or(DamageType::default(), |n| string_to_damage_type(n))
+}
+
+fn get_resistances_component(def: &super::ResistancesDef) -> Resistances {
+    let to_types = |names: &Vec<String>| -> Vec<DamageType> {
+        names.iter().map(|n| string_to_damage_type(n)).collect()
+    };
+    Resistances {
+        resist: to_types(&def.resist),
+        vulnerable: to_types(&def.vulnerable),
+        immune: to_types(&def.immune),
+    }
+}
+
+/// Effect names of the timed statuses, in consumables and traps.
+fn string_to_status(name: &str) -> Option<StatusKind> {
+    match name {
+        "poison" => Some(StatusKind::Poison),
+        "burning" => Some(StatusKind::Burning),
+        "bleeding" => Some(StatusKind::Bleeding),
+        "slow" => Some(StatusKind::Slow),
+        "haste" => Some(StatusKind::Haste),
+        "blind" => Some(StatusKind::Blind),
+        "regeneration" => Some(StatusKind::Regeneration),
+        "paralysis" => Some(StatusKind::Paralysis),
+        _ => None,
+    }
+}
+
+/// "turns" or "turns,potency" ; potency is 1 if omitted.
+fn parse_status(kind: StatusKind, value: &str) -> Status {
+    let mut parts = value.split(',').map(|n| n.trim().parse::<i32>().unwrap());
+    let turns = parts.next().unwrap();
+    let potency = parts.next().unwrap_or(1);
+    Status {
+        kind,
+        turns,
+        potency,
+    }
+}
+
+fn get_on_hit_effects(defs: &Option<Vec<super::OnHitDef>>) -> Vec<OnHit> {
+    let mut on_hit = Vec::new();
+    for def in defs.iter().flatten() {
+        let value = def.value.as_deref().unwrap_or("1");
+        let effect = match def.effect.as_str() {
+            "confusion" => OnHitEffect::Confusion {
+                turns: value.parse::<i32>().unwrap(),
+            },
+            "knockback" => OnHitEffect::Knockback {
+                distance: value.parse::<i32>().unwrap(),
+            },
+            "disarm" => OnHitEffect::Disarm,
+            "life_drain" => OnHitEffect::LifeDrain {
+                percent: value.parse::<i32>().unwrap(),
+            },
+            name => match string_to_status(name) {
+                Some(kind) => {
+                    let status = parse_status(kind, value);
+                    OnHitEffect::Status {
+                        kind,
+                        turns: status.turns,
+                        potency: status.potency,
+                    }
+                }
+                None => {
+                    println!("Warning: on-hit effect {} not implemented.", name);
+                    continue;
+                }
+            },
+        };
+        on_hit.push(OnHit {
+            chance: def.chance,
+            effect,
+        });
+    }
+    on_hit
+}
+
+/// Adds the effect components of a consumable (or a spell) to `eb`.
+fn with_effects<'a>(
+    mut eb: EntityBuilder<'a>,
+    effects: &HashMap<String, String>,
+) -> EntityBuilder<'a> {
+    let mut statuses = Vec::new();
+    for effect in effects.iter() {
+        let effect_name = effect.0.as_str();
+        if let Some(kind) = string_to_status(effect_name) {
+            statuses.push(parse_status(kind, effect.1));
+            continue;
+        }
+        match effect_name {
+            "provides_healing" => {
+                eb = eb.with(ProvidesHealing {
+                    heal_amount: effect.1.parse::<i32>().unwrap(),
+                })
+            }
+            "ranged" => {
+                eb = eb.with(Ranged {
+                    range: effect.1.parse::<i32>().unwrap(),
+                })
+            }
+            "damage" => {
+                eb = eb.with(InflictsDamage {
+                    damage: effect.1.parse::<i32>().unwrap(),
+                    damage_type: damage_type_or_default(effects.get("damage_type")),
+                })
+            }
+            "damage_type" => {}
+            "area_of_effect" => {
+                eb = eb.with(AreaOfEffect {
+                    radius: effect.1.parse::<i32>().unwrap(),
+                })
+            }
+            "confusion" => {
+                eb = eb.with(Confusion {
+                    turns: effect.1.parse::<i32>().unwrap(),
+                })
+            }
+            "magic_mapping" => eb = eb.with(MagicMapper {}),
+            "remove_curse" => eb = eb.with(RemovesCurse {}),
+            "food" => eb = eb.with(ProvidesFood {}),
+            "teach_spell" => {
+                eb = eb.with(TeachesSpell {
+                    spell: effect.1.clone(),
+                })
+            }
+            _ => {
+                println!(
+                    "Warning: consumable effect {} not implemented.",
+                    effect_name
+                );
+            }
+        }
+    }
+    if !statuses.is_empty() {
+        // Les effets viennent d'une HashMap : ordre stable pour les parties rejouées
+        statuses.sort_by_key(|s| s.kind);
+        eb = eb.with(InflictsStatus { statuses });
+    }
+    eb
+}
+
 pub fn spawn_named_item(
     raws: &RawMaster,
     ecs: &mut World,
@@ -203,53 +473,19 @@ pub fn spawn_named_item(
             name: item_template.name.clone(),
         });
 
-        eb = eb.with(crate::components::Item {});
+        eb = eb.with(crate::components::Item {
+            weight_lbs: item_template.weight_lbs.unwrap_or(0.0),
+        });
 
         if let Some(consumable) = &item_template.consumable {
             eb = eb.with(crate::components::Consumable {});
-            for effect in consumable.effects.iter() {
-                let effect_name = effect.0.as_str();
-                match effect_name {
-                    "provides_healing" => {
-                        eb = eb.with(ProvidesHealing {
-                            heal_amount: effect.1.parse::<i32>().unwrap(),
-                        })
-                    }
-                    "ranged" => {
-                        eb = eb.with(Ranged {
-                            range: effect.1.parse::<i32>().unwrap(),
-                        })
-                    }
-                    "damage" => {
-                        eb = eb.with(InflictsDamage {
-                            damage: effect.1.parse::<i32>().unwrap(),
-                        })
-                    }
-                    "area_of_effect" => {
-                        eb = eb.with(AreaOfEffect {
-                            radius: effect.1.parse::<i32>().unwrap(),
-                        })
-                    }
-                    "confusion" => {
-                        eb = eb.with(Confusion {
-                            turns: effect.1.parse::<i32>().unwrap(),
-                        })
-                    }
-                    "magic_mapping" => eb = eb.with(MagicMapper {}),
-                    "food" => eb = eb.with(ProvidesFood {}),
-                    _ => {
-                        println!(
-                            "Warning: consumable effect {} not implemented.",
-                            effect_name
-                        );
-                    }
-                }
-            }
+            eb = eb.with(Quantity { amount: 1 });
+            eb = with_effects(eb, &consumable.effects);
         }
 
         if let Some(weapon) = &item_template.weapon {
             eb = eb.with(Equippable {
-                slot: EquipmentSlot::Melee,
+                slot: weapon_slot(weapon),
             });
             let (n_dice, die_type, bonus) = parse_dice_string(&weapon.base_damage);
             let mut wpn = MeleeWeapon {
@@ -258,12 +494,30 @@ pub fn spawn_named_item(
                 damage_die_type: die_type,
                 damage_bonus: bonus,
                 hit_bonus: weapon.hit_bonus,
+                damage_type: damage_type_or_default(weapon.damage_type.as_ref()),
+                on_hit: get_on_hit_effects(&weapon.on_hit),
             };
             match weapon.attribute.as_str() {
                 "Quickness" => wpn.attribute = WeaponAttribute::Quickness,
                 _ => wpn.attribute = WeaponAttribute::Might,
             }
             eb = eb.with(wpn);
+            if let Ok(range) = weapon.range.parse::<i32>() {
+                eb = eb.with(RangedWeapon {
+                    range,
+                    ammo: weapon.ammo.clone(),
+                });
+            }
+        }
+
+        if let Some(ammo) = &item_template.ammunition {
+            eb = eb.with(Ammunition {
+                ammo_type: ammo.ammo_type.clone(),
+                recover_chance: ammo.recover_chance,
+            });
+            eb = eb.with(Quantity {
+                amount: ammo.quantity,
+            });
         }
 
         if let Some(wearable) = &item_template.wearable {
@@ -273,6 +527,27 @@ pub fn spawn_named_item(
                 slot,
                 armor_class: wearable.armor_class,
             });
+            if let Some(resistances) = &wearable.resistances {
+                eb = eb.with(get_resistances_component(resistances));
+            }
+        }
+
+        if let Some(attributes) = &item_template.attributes {
+            eb = eb.with(AttributeModifiers {
+                might: attributes.might.unwrap_or(0),
+                fitness: attributes.fitness.unwrap_or(0),
+                quickness: attributes.quickness.unwrap_or(0),
+                intelligence: attributes.intelligence.unwrap_or(0),
+            });
+        }
+        if let Some(skills) = &item_template.skills {
+            let mut modifiers = HashMap::new();
+            for (name, value) in skills.iter() {
+                if let Some(skill) = string_to_skill(name) {
+                    modifiers.insert(skill, *value);
+                }
+            }
+            eb = eb.with(SkillModifiers { skills: modifiers });
         }
 
         return Some(eb.build());
@@ -280,6 +555,175 @@ pub fn spawn_named_item(
     None
 }
 
+/// Rolls the curse of an item found at `depth`, then for a weapon or a wearable a
+/// rarity tier, its affixes and its enchantment. Plain items are left as they are.
+pub fn roll_magic_item(
+    raws: &RawMaster,
+    ecs: &World,
+    item: Entity,
+    depth: i32,
+    rng: &mut rltk::RandomNumberGenerator,
+) {
+    let template = match ecs
+        .read_storage::<Name>()
+        .get(item)
+        .and_then(|name| raws.item_index.get(&name.name))
+    {
+        None => return,
+        Some(index) => &raws.raws.items[*index],
+    };
+    if let Some(curse) = &template.curse {
+        if rng.roll_dice(1, 100) <= curse.chance {
+            let penalty = curse.penalty.unwrap_or(0);
+            if let Some(weapon) = ecs.write_storage::<MeleeWeapon>().get_mut(item) {
+                weapon.hit_bonus -= penalty;
+                weapon.damage_bonus -= penalty;
+            }
+            if let Some(wearable) = ecs.write_storage::<Wearable>().get_mut(item) {
+                wearable.armor_class -= penalty as f32;
+            }
+            ecs.write_storage::<Cursed>()
+                .insert(item, Cursed {})
+                .expect("Unable to insert curse");
+        }
+    }
+
+    let item_kind = if template.weapon.is_some() {
+        "weapon"
+    } else if template.wearable.is_some() {
+        "armor"
+    } else {
+        return;
+    };
+
+    // Plus on descend, plus les tiers rares pèsent lourd
+    let mut tiers = RandomTable::new();
+    for tier in raws.raws.rarities.iter().filter(|t| depth >= t.min_depth) {
+        let mut weight = tier.weight;
+        if tier.add_map_depth_to_weight == Some(true) {
+            weight += depth;
+        }
+        tiers = tiers.add(tier.name.clone(), weight);
+    }
+    let tier_name = tiers.roll(rng);
+    let tier = match raws.raws.rarities.iter().find(|t| t.name == tier_name) {
+        None => return,
+        Some(tier) => tier,
+    };
+
+    let mut prefixes: Vec<&Affix> = Vec::new();
+    let mut suffixes: Vec<&Affix> = Vec::new();
+    for (kind, count) in [("prefix", tier.prefixes), ("suffix", tier.suffixes)] {
+        for _ in 0..count {
+            let chosen = if kind == "prefix" {
+                &prefixes
+            } else {
+                &suffixes
+            };
+            let mut table = RandomTable::new();
+            for affix in raws.raws.affixes.iter().filter(|a| {
+                a.kind == kind
+                    && (a.applies_to == item_kind || a.applies_to == "any")
+                    && depth >= a.min_depth
+                    && depth <= a.max_depth
+                    && !chosen.iter().any(|c| c.name == a.name)
+            }) {
+                table = table.add(affix.name.clone(), affix.weight);
+            }
+            let name = table.roll(rng);
+            if let Some(affix) = raws.raws.affixes.iter().find(|a| a.name == name) {
+                if kind == "prefix" {
+                    prefixes.push(affix);
+                } else {
+                    suffixes.push(affix);
+                }
+            }
+        }
+    }
+    let enchantment = if tier.max_enchantment > 0 {
+        rng.roll_dice(1, i32::min(tier.max_enchantment, 1 + depth / 3))
+    } else {
+        0
+    };
+    if prefixes.is_empty() && suffixes.is_empty() && enchantment == 0 {
+        return;
+    }
+
+    let mut name: Vec<String> = prefixes.iter().map(|a| a.name.clone()).collect();
+    name.push(template.name.clone());
+    name.extend(suffixes.iter().map(|a| a.name.clone()));
+    if enchantment > 0 {
+        name.push(format!("+{}", enchantment));
+    }
+    if let Some(item_name) = ecs.write_storage::<Name>().get_mut(item) {
+        item_name.name = name.join(" ");
+    }
+
+    let mut hit_bonus = enchantment;
+    let mut damage_bonus = enchantment;
+    let mut armor_class = enchantment as f32;
+    let mut attributes = AttributeModifiers::default();
+    let mut skills: HashMap<Skill, i32> = HashMap::new();
+    for affix in prefixes.iter().chain(suffixes.iter()) {
+        hit_bonus += affix.hit_bonus.unwrap_or(0);
+        damage_bonus += affix.damage_bonus.unwrap_or(0);
+        armor_class += affix.armor_class.unwrap_or(0.0);
+        if let Some(modifiers) = &affix.attributes {
+            attributes.might += modifiers.might.unwrap_or(0);
+            attributes.fitness += modifiers.fitness.unwrap_or(0);
+            attributes.quickness += modifiers.quickness.unwrap_or(0);
+            attributes.intelligence += modifiers.intelligence.unwrap_or(0);
+        }
+        for (skill, value) in affix.skills.iter().flatten() {
+            if let Some(skill) = string_to_skill(skill) {
+                *skills.entry(skill).or_insert(0) += value;
+            }
+        }
+    }
+
+    if let Some(weapon) = ecs.write_storage::<MeleeWeapon>().get_mut(item) {
+        weapon.hit_bonus += hit_bonus;
+        weapon.damage_bonus += damage_bonus;
+    }
+    if let Some(wearable) = ecs.write_storage::<Wearable>().get_mut(item) {
+        wearable.armor_class += armor_class;
+    }
+    if attributes.might != 0
+        || attributes.fitness != 0
+        || attributes.quickness != 0
+        || attributes.intelligence != 0
+    {
+        let mut storage = ecs.write_storage::<AttributeModifiers>();
+        let modifiers = storage
+            .entry(item)
+            .expect("Unable to get modifiers")
+            .or_insert_with(AttributeModifiers::default);
+        modifiers.might += attributes.might;
+        modifiers.fitness += attributes.fitness;
+        modifiers.quickness += attributes.quickness;
+        modifiers.intelligence += attributes.intelligence;
+    }
+    if !skills.is_empty() {
+        let mut storage = ecs.write_storage::<SkillModifiers>();
+        let modifiers = storage
+            .entry(item)
+            .expect("Unable to get modifiers")
+            .or_insert_with(SkillModifiers::default);
+        for (skill, value) in skills {
+            *modifiers.skills.entry(skill).or_insert(0) += value;
+        }
+    }
+    ecs.write_storage::<Rarity>()
+        .insert(
+            item,
+            Rarity {
+                tier: tier.name.clone(),
+                color: rltk::RGB::from_hex(&tier.color).expect("Invalid RGB"),
+            },
+        )
+        .expect("Unable to insert rarity");
+}
+
 pub fn spawn_named_mob(
     raws: &RawMaster,
     ecs: &mut World,
@@ -393,7 +837,9 @@ pub fn spawn_named_mob(
             1
         };
         let mob_hp = npc_hp(mob_fitness, mob_level);
-        let mob_mana = mana_at_level(mob_int, mob_level);
+        let mob_mana = mob_template
+            .mana
+            .unwrap_or_else(|| mana_at_level(mob_int, mob_level));
 
         let pools = Pools {
             level: mob_level,
@@ -411,25 +857,15 @@ pub fn spawn_named_mob(
 
         let mut skills = Skills {
             skills: HashMap::new(),
+            modifiers: HashMap::new(),
         };
         skills.skills.insert(Skill::Melee, 1);
         skills.skills.insert(Skill::Defense, 1);
         skills.skills.insert(Skill::Magic, 1);
         if let Some(mobskills) = &mob_template.skills {
             for sk in mobskills.iter() {
-                match sk.0.as_str() {
-                    "Melee" => {
-                        skills.skills.insert(Skill::Melee, *sk.1);
-                    }
-                    "Defense" => {
-                        skills.skills.insert(Skill::Defense, *sk.1);
-                    }
-                    "Magic" => {
-                        skills.skills.insert(Skill::Magic, *sk.1);
-                    }
-                    _ => {
-                        println!("Unknown skill referenced: [{}]", sk.0);
-                    }
+                if let Some(skill) = string_to_skill(sk.0) {
+                    skills.skills.insert(skill, *sk.1);
                 }
             }
         }
@@ -455,6 +891,9 @@ pub fn spawn_named_mob(
                         damage_n_dice: n,
                         damage_die_type: d,
                         damage_bonus: b,
+                        range: nattack.range,
+                        damage_type: damage_type_or_default(nattack.damage_type.as_ref()),
+                        on_hit: get_on_hit_effects(&nattack.on_hit),
                     };
                     nature.attacks.push(attack);
                 }
@@ -475,8 +914,35 @@ pub fn spawn_named_mob(
             });
         }
 
-        // Initiative of 2
-        eb = eb.with(Initiative { current: 2 });
+        if let Some(chase) = &mob_template.chase {
+            eb = eb.with(ChaseSettings {
+                max_path: chase.max_path.unwrap_or(DEFAULT_CHASE_PATH),
+                search_turns: chase.search_turns.unwrap_or(DEFAULT_SEARCH_TURNS),
+            });
+        }
+
+        if let Some(resistances) = &mob_template.resistances {
+            eb = eb.with(get_resistances_component(resistances));
+        }
+
+        if let Some(spells) = &mob_template.spells {
+            eb = eb.with(KnownSpells {
+                spells: spells.clone(),
+            });
+        }
+
+        if mob_template.removes_curses {
+            eb = eb.with(RemovesCurse {});
+        }
+
+        // Quelques ticks avant son premier tour
+        eb = eb.with(Energy {
+            current: ACTION_COST - 2 * NORMAL_SPEED,
+            base_speed: mob_template.speed.unwrap_or(NORMAL_SPEED),
+            burden: 0,
+            faster_than_player: false,
+        });
+        eb = eb.with(EquipmentChanged {});
 
         //If there isn't one faction, we'll automatically apply "mindless" to the mob:
         if let Some(faction) = &mob_template.faction {
@@ -546,17 +1012,29 @@ pub fn spawn_named_prop(
         }
         if let Some(entry_trigger) = &prop_template.entry_trigger {
             eb = eb.with(EntryTrigger {});
+            let mut statuses = Vec::new();
             for effect in entry_trigger.effects.iter() {
+                if let Some(kind) = string_to_status(effect.0) {
+                    statuses.push(parse_status(kind, effect.1));
+                    continue;
+                }
                 match effect.0.as_str() {
                     "damage" => {
                         eb = eb.with(InflictsDamage {
                             damage: effect.1.parse::<i32>().unwrap(),
+                            damage_type: damage_type_or_default(
+                                entry_trigger.effects.get("damage_type"),
+                            ),
                         })
                     }
                     "single_activation" => eb = eb.with(SingleActivation {}),
                     _ => {}
                 }
             }
+            if !statuses.is_empty() {
+                statuses.sort_by_key(|s| s.kind);
+                eb = eb.with(InflictsStatus { statuses });
+            }
         }
 
         return Some(eb.build());
@@ -564,6 +1042,66 @@ pub fn spawn_named_prop(
     None
 }
 
+/// Spawns one entity per spell of the raws, which the casters refer to by name.
+pub fn spawn_all_spells(raws: &RawMaster, ecs: &mut World) {
+    for spell in raws.raws.spells.iter() {
+        let mut eb = ecs.create_entity().marked::<SimpleMarker<SerializeMe>>();
+        eb = eb.with(Name {
+            name: spell.name.clone(),
+        });
+        eb = eb.with(SpellTemplate {
+            mana_cost: spell.mana_cost,
+            magic_skill: spell.magic_skill,
+        });
+        match spell.target.as_str() {
+            "self" => {}
+            "single" | "area" => {
+                eb = eb.with(Ranged {
+                    range: spell.range.expect("Targeted spell without a range"),
+                });
+                if spell.target == "area" {
+                    eb = eb.with(AreaOfEffect {
+                        radius: spell.radius.expect("Area spell without a radius"),
+                    });
+                }
+            }
+            _ => println!(
+                "Warning: spell target {} not implemented ({}).",
+                spell.target, spell.name
+            ),
+        }
+        eb = with_effects(eb, &spell.effects);
+        eb.build();
+    }
+}
+
+pub fn get_backgrounds(raws: &RawMaster) -> &[Background] {
+    &raws.raws.backgrounds
+}
+
+/// (name, kind) of the items that need identifying, in the order of the raws.
+pub fn get_unidentified_items(raws: &RawMaster) -> Vec<(String, String)> {
+    raws.raws
+        .items
+        .iter()
+        .filter_map(|item| {
+            item.unidentified
+                .as_ref()
+                .map(|kind| (item.name.clone(), kind.clone()))
+        })
+        .collect()
+}
+
+pub fn find_spell_entity(ecs: &World, name: &str) -> Option<Entity> {
+    let entities = ecs.entities();
+    let templates = ecs.read_storage::<SpellTemplate>();
+    let names = ecs.read_storage::<Name>();
+    (&entities, &templates, &names)
+        .join()
+        .find(|(_, _, n)| n.name == name)
+        .map(|(entity, _, _)| entity)
+}
+
 pub fn spawn_named_entity(
     raws: &RawMaster,
     ecs: &mut World,
@@ -581,7 +1119,8 @@ pub fn spawn_named_entity(
     None
 }
 
-pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> RandomTable {
+/// Spawn table of a level: the entries without `branches` only spawn in the main branch.
+pub fn get_spawn_table_for_depth(raws: &RawMaster, branch: &str, depth: i32) -> RandomTable {
     use super::SpawnTableEntry;
 
     let available_options: Vec<&SpawnTableEntry> = raws
@@ -589,6 +1128,10 @@ pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> RandomTable {
         .spawn_table
         .iter()
         .filter(|a| depth >= a.min_depth && depth <= a.max_depth)
+        .filter(|a| match &a.branches {
+            Some(branches) => branches.iter().any(|b| b == branch),
+            None => branch == MAIN_BRANCH,
+        })
         .collect();
 
     let mut rt = RandomTable::new();
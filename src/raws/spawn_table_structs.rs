@@ -7,4 +7,6 @@ pub struct SpawnTableEntry {
     pub min_depth: i32,
     pub max_depth: i32,
     pub add_map_depth_to_weight: Option<bool>,
+    /// Branches where this entry spawns, the main branch only when missing.
+    pub branches: Option<Vec<String>>,
 }
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct Spell {
+    pub name: String,
+    pub mana_cost: i32,
+    /// Magic skill needed to learn (and cast) it.
+    pub magic_skill: i32,
+    /// "self", "single" (the tile aimed at) or "area" (around the tile aimed at).
+    pub target: String,
+    pub range: Option<i32>,
+    pub radius: Option<i32>,
+    /// Same effects as a consumable.
+    pub effects: HashMap<String, String>,
+}
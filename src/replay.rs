@@ -0,0 +1,155 @@
+use super::*;
+use rltk::{Console, Rltk, VirtualKeyCode, RGB};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+
+const REPLAY_STEP_MS: f32 = 150.0;
+
+/// Spreads the seeds of successive turns apart.
+const TURN_SEED_MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Toutes les commandes d'une partie, rejouables à partir de sa graine.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    /// Le personnage créé en début de partie, celui par défaut sinon
+    #[serde(default)]
+    pub character: Option<CharacterSheet>,
+    pub commands: Vec<PlayerCommand>,
+    /// Commands played so far, recorded or not
+    #[serde(default)]
+    pub turn: u64,
+    // Faux après le chargement d'une sauvegarde qui n'avait pas son replay
+    #[serde(default)]
+    pub recording: bool,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Replay {
+        Replay {
+            seed,
+            character: None,
+            commands: Vec::new(),
+            turn: 0,
+            recording: true,
+        }
+    }
+
+    pub fn record(&mut self, command: PlayerCommand) {
+        self.turn += 1;
+        if self.recording {
+            self.commands.push(command);
+        }
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording = false;
+        self.commands.clear();
+    }
+
+    //Won't compile if use of Web Assembly because wasm can't write file
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self, _path: &str) {}
+
+    /// Writes the recording, if this game can be replayed from its seed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &str) {
+        if !self.recording {
+            return;
+        }
+        let data = serde_json::to_string(self).expect("Unable to serialize the replay");
+        if fs::write(path, data).is_err() {
+            println!("Warning : unable to write the replay to {}", path);
+        }
+    }
+
+    pub fn load(path: &str) -> Replay {
+        let data = fs::read_to_string(path).expect("Unable to read the replay file");
+        serde_json::from_str(&data).expect("Unable to parse the replay file")
+    }
+}
+
+/// Reseeds the RNG from the seed of the run and the number of commands played.
+/// Done before every command, so that a loaded game draws the same numbers as
+/// the game that was saved.
+pub fn reseed_rng(ecs: &mut World) {
+    let seed = {
+        let replay = ecs.fetch::<Replay>();
+        replay.seed ^ replay.turn.wrapping_mul(TURN_SEED_MIX)
+    };
+    ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
+}
+
+/// Lecture d'un replay dans la fenêtre : Espace pour pause, F pour l'avance rapide,
+/// Echap pour reprendre la main.
+pub struct Playback {
+    commands: VecDeque<PlayerCommand>,
+    pub paused: bool,
+    pub fast_forward: bool,
+    timer: f32,
+}
+
+impl Playback {
+    pub fn new(replay: &Replay) -> Playback {
+        Playback {
+            commands: replay.commands.iter().copied().collect(),
+            paused: false,
+            fast_forward: false,
+            timer: 0.0,
+        }
+    }
+}
+
+/// Takes the place of `player_input` while a replay is playing.
+pub fn playback_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
+    let command = {
+        let playback = gs.playback.as_mut().unwrap();
+        match ctx.key {
+            Some(VirtualKeyCode::Space) => playback.paused = !playback.paused,
+            Some(VirtualKeyCode::F) => playback.fast_forward = !playback.fast_forward,
+            Some(VirtualKeyCode::Escape) => playback.commands.clear(),
+            _ => {}
+        }
+
+        let status = if playback.paused {
+            "REPLAY - paused (SPACE to resume, ESCAPE to take over)"
+        } else if playback.fast_forward {
+            "REPLAY - fast forward (F for normal speed)"
+        } else {
+            "REPLAY (SPACE to pause, F to fast forward)"
+        };
+        ctx.print_color(
+            1,
+            1,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            status,
+        );
+
+        playback.timer += ctx.frame_time_ms;
+        if playback.paused || (!playback.fast_forward && playback.timer < REPLAY_STEP_MS) {
+            return RunState::AwaitingInput;
+        }
+        playback.timer = 0.0;
+        playback.commands.pop_front()
+    };
+
+    match command {
+        None => {
+            gs.playback = None;
+            let mut gamelog = gs.ecs.fetch_mut::<GameLog>();
+            gamelog
+                .entries
+                .insert(0, "The replay is over, you have the control.".to_string());
+            RunState::AwaitingInput
+        }
+        Some(command) => match apply_command(gs, command) {
+            // Le replay ne contient que des cibles déjà choisies
+            RunState::ShowTargeting { .. }
+            | RunState::ShowFireTargeting { .. }
+            | RunState::ShowSpellTargeting { .. } => RunState::AwaitingInput,
+            newrunstate => newrunstate,
+        },
+    }
+}
@@ -2,19 +2,24 @@ extern crate rltk;
 use rltk::{RandomNumberGenerator, RGB};
 extern crate specs;
 use super::{
-    random_table::RandomTable, raws::*, Attribute, Attributes, Faction, HungerClock, HungerState,
-    Initiative, LightSource, Map, Name, Player, Pool, Pools, Position, Rect, SerializeMe, Skill,
-    Skills, TileType, Viewshed,
+    random_table::RandomTable, raws::*, Attribute, Attributes, CharacterSheet, Energy,
+    EquipmentChanged, Equipped, Faction, HungerClock, HungerState, InBackpack, KnownSpells,
+    LightSource, Map, Name, Player, Pool, Pools, Position, Rect, SerializeMe, Skill, Skills,
+    TileType, Viewshed,
+};
+use crate::{
+    attr_bonus, mana_at_level, player_hp_at_level, recompute_player_pools, ACTION_COST,
+    NORMAL_SPEED,
 };
-use crate::{attr_bonus, mana_at_level, player_hp_at_level};
 use specs::prelude::*;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Spawns the player and returns his/her entity object.
 pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
     let mut skills = Skills {
         skills: HashMap::new(),
+        modifiers: HashMap::new(),
     };
     skills.skills.insert(Skill::Melee, 1);
     skills.skills.insert(Skill::Defense, 1);
@@ -70,8 +75,8 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
         .with(skills)
         .with(Pools {
             hit_points: Pool {
-                current: player_hp_at_level(110, 1),
-                max: player_hp_at_level(110, 1),
+                current: player_hp_at_level(11, 1),
+                max: player_hp_at_level(11, 1),
             },
             mana: Pool {
                 current: mana_at_level(11, 1),
@@ -84,10 +89,19 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             color: rltk::RGB::from_f32(1.0, 1.0, 0.5),
             range: 8,
         })
-        .with(Initiative { current: 0 })
+        .with(Energy {
+            current: ACTION_COST,
+            base_speed: NORMAL_SPEED,
+            burden: 0,
+            faster_than_player: false,
+        })
+        .with(EquipmentChanged {})
         .with(Faction {
             name: "Player".to_string(),
         })
+        .with(KnownSpells {
+            spells: vec!["Zap".to_string()],
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -132,10 +146,118 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
     player
 }
 
+fn creation_attribute(base: i32) -> Attribute {
+    Attribute {
+        base,
+        modifiers: 0,
+        bonus: attr_bonus(base),
+    }
+}
+
+/// Turns the default character of `player` into the one of `sheet` : name,
+/// attributes, and everything its background brings.
+pub fn apply_character(ecs: &mut World, sheet: &CharacterSheet) {
+    let player = *ecs.fetch::<Entity>();
+    let raws = RAWS.lock().unwrap();
+    let background = match get_backgrounds(&raws)
+        .iter()
+        .find(|b| b.name == sheet.background)
+    {
+        None => {
+            println!("Warning: unknown background [{}]", sheet.background);
+            return;
+        }
+        Some(background) => background,
+    };
+
+    // Le paquetage par défaut laisse la place à celui du passé choisi
+    let starting_kit: Vec<Entity> = {
+        let entities = ecs.entities();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let equipped = ecs.read_storage::<Equipped>();
+        entities
+            .join()
+            .filter(|e| {
+                backpack.get(*e).is_some_and(|b| b.owner == player)
+                    || equipped.get(*e).is_some_and(|eq| eq.owner == player)
+            })
+            .collect()
+    };
+    ecs.delete_entities(&starting_kit)
+        .expect("Unable to delete the starting kit");
+
+    let attributes = Attributes {
+        might: creation_attribute(sheet.attributes[0]),
+        fitness: creation_attribute(sheet.attributes[1]),
+        quickness: creation_attribute(sheet.attributes[2]),
+        intelligence: creation_attribute(sheet.attributes[3]),
+    };
+    let mut skills = Skills {
+        skills: HashMap::new(),
+        modifiers: HashMap::new(),
+    };
+    skills.skills.insert(Skill::Melee, 1);
+    skills.skills.insert(Skill::Defense, 1);
+    skills.skills.insert(Skill::Magic, 1);
+    for (name, value) in background.skills.iter() {
+        if let Some(skill) = string_to_skill(name) {
+            skills.skills.insert(skill, *value);
+        }
+    }
+    {
+        let mut pools = ecs.write_storage::<Pools>();
+        let player_pools = pools.get_mut(player).unwrap();
+        recompute_player_pools(player_pools, &attributes);
+        player_pools.hit_points.current = player_pools.hit_points.max;
+        player_pools.mana.current = player_pools.mana.max;
+    }
+
+    let name = if sheet.name.is_empty() {
+        "Player".to_string()
+    } else {
+        sheet.name.clone()
+    };
+    ecs.write_storage::<Name>()
+        .insert(player, Name { name })
+        .expect("Unable to insert name");
+    ecs.write_storage::<Attributes>()
+        .insert(player, attributes)
+        .expect("Unable to insert attributes");
+    ecs.write_storage::<Skills>()
+        .insert(player, skills)
+        .expect("Unable to insert skills");
+    ecs.write_storage::<Faction>()
+        .insert(
+            player,
+            Faction {
+                name: background.faction.clone(),
+            },
+        )
+        .expect("Unable to insert faction");
+    ecs.write_storage::<KnownSpells>()
+        .insert(
+            player,
+            KnownSpells {
+                spells: background.spells.clone().unwrap_or_default(),
+            },
+        )
+        .expect("Unable to insert known spells");
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(player, EquipmentChanged {})
+        .expect("Unable to insert equipment changed");
+
+    for item in background.equipped.iter() {
+        spawn_named_entity(&raws, ecs, item, SpawnType::Equipped { by: player });
+    }
+    for item in background.carried.iter() {
+        spawn_named_entity(&raws, ecs, item, SpawnType::Carried { by: player });
+    }
+}
+
 const MAX_MONSTERS: i32 = 4;
 
-fn room_table(map_depth: i32) -> RandomTable {
-    get_spawn_table_for_depth(&RAWS.lock().unwrap(), map_depth)
+fn room_table(branch: &str, map_depth: i32) -> RandomTable {
+    get_spawn_table_for_depth(&RAWS.lock().unwrap(), branch, map_depth)
 }
 
 /// Fills a room with stuff!
@@ -164,14 +286,14 @@ pub fn spawn_room(
 
 /// Fills a region with stuff!
 pub fn spawn_region(
-    _map: &Map,
+    map: &Map,
     rng: &mut RandomNumberGenerator,
     area: &[usize],
     map_depth: i32,
     spawn_list: &mut Vec<(usize, String)>,
 ) {
-    let spawn_table = room_table(map_depth);
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
+    let spawn_table = room_table(&map.branch, map_depth);
+    let mut spawn_points: BTreeMap<usize, String> = BTreeMap::new();
     let mut areas: Vec<usize> = Vec::from(area);
 
     // Scope to keep the borrow checker happy
@@ -203,21 +325,20 @@ pub fn spawn_region(
     }
 }
 
-/// Spawns a named entity (name in tuple.1) at the location in (tuple.0)
-pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
+/// Spawns a named entity (name in tuple.1) at the location in (tuple.0).
+/// Weapons and armor may roll affixes for the depth of the map.
+pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String), rng: &mut RandomNumberGenerator) {
     let map = ecs.fetch::<Map>();
     let width = map.width as usize;
+    let depth = map.depth;
     let x = (*spawn.0 % width) as i32;
     let y = (*spawn.0 / width) as i32;
     std::mem::drop(map);
 
-    let spawn_result = spawn_named_entity(
-        &RAWS.lock().unwrap(),
-        ecs,
-        &spawn.1,
-        SpawnType::AtPosition { x, y },
-    );
-    if spawn_result.is_some() {
+    let raws = RAWS.lock().unwrap();
+    let spawn_result = spawn_named_entity(&raws, ecs, spawn.1, SpawnType::AtPosition { x, y });
+    if let Some(entity) = spawn_result {
+        roll_magic_item(&raws, ecs, entity, depth, rng);
         return;
     }
 
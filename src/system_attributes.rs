@@ -0,0 +1,117 @@
+use super::{
+    attr_bonus, recompute_player_pools, Attribute, AttributeModifiers, Attributes,
+    EquipmentChanged, Equipped, Player, Pools, Skill, SkillModifiers, Skills, StatusChanged,
+    StatusEffects,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Modifiers summed from equipment and statuses, in the order of `Attributes`.
+#[derive(Default)]
+struct Totals {
+    attributes: [i32; 4],
+    skills: HashMap<Skill, i32>,
+}
+
+fn set_modifiers(attribute: &mut Attribute, modifiers: i32) {
+    attribute.modifiers = modifiers;
+    attribute.bonus = attr_bonus(attribute.base + modifiers);
+}
+
+/// Recomputes the attribute and skill modifiers of whoever changed equipment or
+/// statuses, then the max HP and mana of the player if Fitness or Intelligence moved.
+pub struct AttributeModifierSystem {}
+
+impl<'a> System<'a> for AttributeModifierSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, EquipmentChanged>,
+        WriteStorage<'a, StatusChanged>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, AttributeModifiers>,
+        ReadStorage<'a, SkillModifiers>,
+        ReadStorage<'a, StatusEffects>,
+        WriteStorage<'a, Attributes>,
+        WriteStorage<'a, Skills>,
+        WriteStorage<'a, Pools>,
+        ReadStorage<'a, Player>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            equip_dirty,
+            mut status_dirty,
+            equipped,
+            attribute_modifiers,
+            skill_modifiers,
+            statuses,
+            mut attributes,
+            mut skills,
+            mut pools,
+            players,
+        ) = data;
+
+        let mut totals: HashMap<Entity, Totals> = HashMap::new();
+        for (entity, _dirty) in (&entities, &equip_dirty).join() {
+            totals.insert(entity, Totals::default());
+        }
+        for (entity, _dirty) in (&entities, &status_dirty).join() {
+            totals.insert(entity, Totals::default());
+        }
+        if totals.is_empty() {
+            return;
+        }
+
+        for (worn, modifiers) in (&equipped, &attribute_modifiers).join() {
+            if let Some(total) = totals.get_mut(&worn.owner) {
+                total.attributes[0] += modifiers.might;
+                total.attributes[1] += modifiers.fitness;
+                total.attributes[2] += modifiers.quickness;
+                total.attributes[3] += modifiers.intelligence;
+            }
+        }
+        for (worn, modifiers) in (&equipped, &skill_modifiers).join() {
+            if let Some(total) = totals.get_mut(&worn.owner) {
+                for (skill, value) in modifiers.skills.iter() {
+                    *total.skills.entry(*skill).or_insert(0) += value;
+                }
+            }
+        }
+
+        for (entity, total) in totals.iter_mut() {
+            if let Some(status_effects) = statuses.get(*entity) {
+                for status in status_effects.statuses.iter() {
+                    total.attributes[2] += status.kind.quickness_modifier();
+                    if let Some((skill, value)) = status.kind.skill_modifier() {
+                        *total.skills.entry(skill).or_insert(0) += value;
+                    }
+                }
+            }
+
+            if let Some(skill_set) = skills.get_mut(*entity) {
+                skill_set.modifiers = total.skills.clone();
+            }
+            let attr = match attributes.get_mut(*entity) {
+                None => continue,
+                Some(attr) => attr,
+            };
+            let pools_changed = attr.fitness.modifiers != total.attributes[1]
+                || attr.intelligence.modifiers != total.attributes[3];
+            set_modifiers(&mut attr.might, total.attributes[0]);
+            set_modifiers(&mut attr.fitness, total.attributes[1]);
+            set_modifiers(&mut attr.quickness, total.attributes[2]);
+            set_modifiers(&mut attr.intelligence, total.attributes[3]);
+
+            // Les PV des monstres viennent des raws, seuls ceux du joueur suivent
+            if pools_changed && players.get(*entity).is_some() {
+                if let Some(player_pools) = pools.get_mut(*entity) {
+                    recompute_player_pools(player_pools, attr);
+                }
+            }
+        }
+
+        status_dirty.clear();
+    }
+}
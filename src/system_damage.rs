@@ -1,99 +1,9 @@
 extern crate specs;
 use super::{
-    gamelog::GameLog, system_particle::ParticleBuilder, Attributes, Equipped, InBackpack,
-    LootTable, Map, Name, Player, Pools, Position, RunState, SufferDamage,
+    gamelog::GameLog, Equipped, InBackpack, LootTable, Name, Player, Pools, Position, RunState,
 };
-use crate::gamesystem::{mana_at_level, player_hp_at_level};
 use specs::prelude::*;
 
-pub struct DamageSystem {}
-
-impl<'a> System<'a> for DamageSystem {
-    #[allow(clippy::type_complexity)]
-    type SystemData = (
-        WriteStorage<'a, Pools>,
-        WriteStorage<'a, SufferDamage>,
-        ReadStorage<'a, Position>,
-        WriteExpect<'a, Map>,
-        Entities<'a>,
-        ReadExpect<'a, Entity>,
-        ReadStorage<'a, Attributes>,
-        WriteExpect<'a, GameLog>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadExpect<'a, rltk::Point>,
-    );
-
-    fn run(&mut self, data: Self::SystemData) {
-        let (
-            mut stats,
-            mut damage,
-            positions,
-            mut map,
-            entities,
-            player,
-            attributes,
-            mut log,
-            mut particles,
-            player_pos,
-        ) = data;
-        let mut xp_gain = 0;
-
-        for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
-            for dmg in damage.amount.iter() {
-                stats.hit_points.current -= dmg.0;
-                let pos = positions.get(entity);
-                if let Some(pos) = pos {
-                    let idx = map.xy_idx(pos.x, pos.y);
-                    map.bloodstains.insert(idx);
-                }
-
-                if stats.hit_points.current < 1 && dmg.1 {
-                    xp_gain += stats.level * 100;
-                }
-            }
-        }
-
-        if xp_gain != 0 {
-            let mut player_stats = stats.get_mut(*player).unwrap();
-            let player_attributes = attributes.get(*player).unwrap();
-            player_stats.xp += xp_gain;
-            if player_stats.xp >= player_stats.level * 1000 {
-                // We've gone up a level!
-                player_stats.level += 1;
-                log.entries.insert(
-                    0,
-                    format!("Congratulations, you are now level {}", player_stats.level),
-                );
-                player_stats.hit_points.max = player_hp_at_level(
-                    player_attributes.fitness.base + player_attributes.fitness.modifiers,
-                    player_stats.level,
-                );
-                player_stats.hit_points.current = player_stats.hit_points.max;
-                player_stats.mana.max = mana_at_level(
-                    player_attributes.intelligence.base + player_attributes.intelligence.modifiers,
-                    player_stats.level,
-                );
-                player_stats.mana.current = player_stats.mana.max;
-
-                for i in 0..10 {
-                    if player_pos.y - i > 1 {
-                        particles.request(
-                            player_pos.x,
-                            player_pos.y - i,
-                            rltk::to_cp437('░'),
-                            rltk::RGB::named(rltk::GOLD),
-                            rltk::RGB::named(rltk::BLACK),
-                            400.0,
-                        );
-                    }
-                }
-            }
-        }
-
-        damage.clear();
-    }
-}
-
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
     // Using a scope to make the borrow checker happy
@@ -178,9 +88,11 @@ pub fn delete_the_dead(ecs: &mut World) {
     }
 
     {
+        let depth = ecs.fetch::<crate::Map>().depth;
         for drop in to_spawn.iter() {
-            crate::raws::spawn_named_item(
-                &crate::raws::RAWS.lock().unwrap(),
+            let raws = crate::raws::RAWS.lock().unwrap();
+            let item = crate::raws::spawn_named_item(
+                &raws,
                 ecs,
                 &drop.0,
                 crate::raws::SpawnType::AtPosition {
@@ -188,6 +100,10 @@ pub fn delete_the_dead(ecs: &mut World) {
                     y: drop.1.y,
                 },
             );
+            if let Some(item) = item {
+                let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+                crate::raws::roll_magic_item(&raws, ecs, item, depth, &mut rng);
+            }
         }
     }
 
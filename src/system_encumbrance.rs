@@ -0,0 +1,93 @@
+use super::{
+    gamelog::GameLog, Attributes, Energy, EquipmentChanged, Equipped, InBackpack, Item, Quantity,
+    Wearable, CARRY_LBS_PER_MIGHT, HEAVY_ARMOR_LBS, OVERLOAD_SPEED_PENALTY,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Recomputes the burden of whoever's equipment changed : a point of speed per
+/// piece of heavy armor worn, and more when carrying over what its Might allows.
+pub struct EncumbranceSystem {}
+
+impl<'a> System<'a> for EncumbranceSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, EquipmentChanged>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Wearable>,
+        ReadStorage<'a, Quantity>,
+        ReadStorage<'a, Attributes>,
+        WriteStorage<'a, Energy>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut equip_dirty,
+            items,
+            backpacks,
+            equipped,
+            wearables,
+            quantities,
+            attributes,
+            mut energies,
+            player,
+            mut log,
+        ) = data;
+
+        // (poids porté, pièces d'armure lourdes)
+        let mut loads: HashMap<Entity, (f32, i32)> = HashMap::new();
+        for (entity, _dirty) in (&entities, &equip_dirty).join() {
+            loads.insert(entity, (0.0, 0));
+        }
+        if loads.is_empty() {
+            return;
+        }
+
+        for (item_entity, item, carried) in (&entities, &items, &backpacks).join() {
+            if let Some(load) = loads.get_mut(&carried.owner) {
+                let amount = quantities.get(item_entity).map_or(1, |q| q.amount);
+                load.0 += item.weight_lbs * amount as f32;
+            }
+        }
+        for (item_entity, item, worn) in (&entities, &items, &equipped).join() {
+            if let Some(load) = loads.get_mut(&worn.owner) {
+                load.0 += item.weight_lbs;
+                if wearables.get(item_entity).is_some() && item.weight_lbs >= HEAVY_ARMOR_LBS {
+                    load.1 += 1;
+                }
+            }
+        }
+
+        for (entity, (carried, heavy_armor)) in loads.iter() {
+            let energy = match energies.get_mut(*entity) {
+                None => continue,
+                Some(energy) => energy,
+            };
+            let might = attributes
+                .get(*entity)
+                .map_or(11, |attr| attr.might.base + attr.might.modifiers);
+            let mut burden = *heavy_armor;
+            if *carried > might as f32 * CARRY_LBS_PER_MIGHT {
+                burden += OVERLOAD_SPEED_PENALTY;
+            }
+
+            if *entity == *player {
+                if burden > energy.burden {
+                    log.entries
+                        .insert(0, "Your burden slows you down.".to_string());
+                } else if burden < energy.burden {
+                    log.entries.insert(0, "You feel lighter.".to_string());
+                }
+            }
+            energy.burden = burden;
+        }
+
+        equip_dirty.clear();
+    }
+}
@@ -8,13 +8,13 @@ impl<'a> System<'a> for HungerSystem {
         Entities<'a>,
         WriteStorage<'a, HungerClock>,
         ReadExpect<'a, Entity>,
-        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, EffectQueue>,
         WriteExpect<'a, GameLog>,
         ReadStorage<'a, MyTurn>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut hunger_clock, player_entity, mut inflict_damage, mut log, turns) = data;
+        let (entities, mut hunger_clock, player_entity, mut effects, mut log, turns) = data;
 
         for (entity, mut clock, _myturn) in (&entities, &mut hunger_clock, &turns).join() {
             clock.duration -= 1;
@@ -51,7 +51,14 @@ impl<'a> System<'a> for HungerSystem {
                                     .to_string(),
                             );
                         }
-                        SufferDamage::new_damage(&mut inflict_damage, entity, 1, false);
+                        effects.add_effect(
+                            None,
+                            EffectType::Damage {
+                                amount: 1,
+                                damage_type: None,
+                            },
+                            Targets::Single { target: entity },
+                        );
                     }
                 }
             }
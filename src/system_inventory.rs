@@ -3,6 +3,7 @@ use super::*;
 pub struct ItemCollectionSystem {}
 
 impl<'a> System<'a> for ItemCollectionSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
@@ -10,14 +11,74 @@ impl<'a> System<'a> for ItemCollectionSystem {
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, InBackpack>,
+        Entities<'a>,
+        WriteStorage<'a, Quantity>,
+        WriteStorage<'a, EquipmentChanged>,
+        WriteStorage<'a, Energy>,
+        ReadExpect<'a, KnownItems>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Rarity>,
+        ReadStorage<'a, Cursed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) =
-            data;
+        let (
+            player_entity,
+            mut gamelog,
+            mut wants_pickup,
+            mut positions,
+            names,
+            mut backpack,
+            entities,
+            mut quantities,
+            mut dirty,
+            mut energies,
+            known_items,
+            equipped,
+            rarities,
+            cursed,
+        ) = data;
 
         for pickup in wants_pickup.join() {
             positions.remove(pickup.item);
+            dirty
+                .insert(pickup.collected_by, EquipmentChanged {})
+                .expect("Unable to insert");
+            if let Some(energy) = energies.get_mut(pickup.collected_by) {
+                energy.spend(ActionKind::PickUp);
+            }
+
+            // Une pile rejoint la pile du même nom portée ou dans le sac,
+            // sauf si l'une des deux est maudite ou a des affixes
+            let name = &names.get(pickup.item).unwrap().name;
+            let plain = |item: Entity| rarities.get(item).is_none() && cursed.get(item).is_none();
+            let stack = if quantities.get(pickup.item).is_some() && plain(pickup.item) {
+                (&entities, &quantities, &names)
+                    .join()
+                    .filter(|(e, _, _)| {
+                        backpack.get(*e).map(|b| b.owner) == Some(pickup.collected_by)
+                            || equipped.get(*e).map(|q| q.owner) == Some(pickup.collected_by)
+                    })
+                    .find(|(e, _, n)| &n.name == name && plain(*e))
+                    .map(|(e, _, _)| e)
+            } else {
+                None
+            };
+            if let Some(stack) = stack {
+                let amount = quantities.get(pickup.item).map_or(1, |q| q.amount);
+                if let Some(qty) = quantities.get_mut(stack) {
+                    qty.amount += amount;
+                }
+                entities.delete(pickup.item).expect("Unable to delete");
+                if pickup.collected_by == *player_entity {
+                    gamelog.entries.insert(
+                        0,
+                        format!("You pick up the {}.", known_items.display_name(name)),
+                    );
+                }
+                continue;
+            }
+
             backpack
                 .insert(
                     pickup.item,
@@ -30,7 +91,10 @@ impl<'a> System<'a> for ItemCollectionSystem {
             if pickup.collected_by == *player_entity {
                 gamelog.entries.insert(
                     0,
-                    format!("You pick up the {}.", names.get(pickup.item).unwrap().name),
+                    format!(
+                        "You pick up the {}.",
+                        known_items.display_name(&names.get(pickup.item).unwrap().name)
+                    ),
                 );
             }
         }
@@ -50,28 +114,18 @@ impl<'a> System<'a> for ItemUseSystem {
         Entities<'a>,
         WriteStorage<'a, WantsToUseItem>,
         ReadStorage<'a, Name>,
-        ReadStorage<'a, Targetable>,
-        ReadStorage<'a, Consumable>,
-        ReadStorage<'a, ProvidesHealing>,
-        ReadStorage<'a, InflictsDamage>,
-        WriteStorage<'a, Pools>,
-        WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, AreaOfEffect>,
-        WriteStorage<'a, Confusion>,
         ReadStorage<'a, Equippable>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
-        ReadStorage<'a, ProvidesFood>,
-        WriteStorage<'a, HungerClock>,
-        ReadStorage<'a, MagicMapper>,
-        WriteExpect<'a, RunState>,
+        WriteExpect<'a, EffectQueue>,
+        WriteStorage<'a, EquipmentChanged>,
+        WriteStorage<'a, Energy>,
+        ReadStorage<'a, Cursed>,
+        ReadExpect<'a, KnownItems>,
     );
 
-    #[allow(clippy::cognitive_complexity)]
     fn run(&mut self, data: Self::SystemData) {
-        #[allow(clippy::type_complexity)]
         let (
             player_entity,
             mut gamelog,
@@ -79,112 +133,67 @@ impl<'a> System<'a> for ItemUseSystem {
             entities,
             mut wants_use,
             names,
-            targetables,
-            consumables,
-            healing,
-            inflict_damage,
-            mut combat_stats,
-            mut suffer_damage,
             aoe,
-            mut confused,
             equippable,
             mut equipped,
             mut backpack,
-            mut particle_builder,
-            positions,
-            provides_food,
-            mut hunger_clocks,
-            magic_mapper,
-            mut runstate,
+            mut effects,
+            mut dirty,
+            mut energies,
+            cursed,
+            known_items,
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
-            let mut used_item: bool = true;
-
-            //Targeting
-            let mut targets: Vec<Entity> = Vec::new();
-            match useitem.target {
+            //Si equippable, on l'équip, et deséquipe ce qui est présent actuellement
+            let item_equippable = equippable.get(useitem.item);
+            match item_equippable {
                 None => {
-                    targets.push(*player_entity);
-                }
-                Some(target) => {
-                    let area_effect = aoe.get(useitem.item);
-                    match area_effect {
-                        None => {
-                            //Single target
-                            let idx = map.xy_idx(target.x, target.y);
-                            for mob in map.tile_content[idx].iter() {
-                                let targetable = targetables.get(*mob);
-                                match targetable {
-                                    None => {}
-                                    Some(_) => {
-                                        if !targets.contains(mob) {
-                                            targets.push(*mob);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Some(area_effect) => {
-                            //Aoe
-                            let mut blast_tiles =
-                                rltk::field_of_view(target, area_effect.radius, &*map);
-                            blast_tiles.retain(|p| {
-                                p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
-                            });
-                            for tile_idx in blast_tiles.iter() {
-                                let idx = map.xy_idx(tile_idx.x, tile_idx.y);
-                                for mob in map.tile_content[idx].iter() {
-                                    let targetable = targetables.get(*mob);
-                                    match targetable {
-                                        None => {}
-                                        Some(_) => {
-                                            if !targets.contains(mob) {
-                                                targets.push(*mob);
-                                            }
-                                        }
-                                    }
-                                }
-                                particle_builder.request(
-                                    tile_idx.x,
-                                    tile_idx.y,
-                                    rltk::to_cp437('░'),
-                                    rltk::RGB::named(rltk::ORANGE),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    200.0,
-                                );
-                            }
-                        }
+                    if let Some(energy) = energies.get_mut(entity) {
+                        energy.spend(ActionKind::UseItem);
                     }
-                }
-            }
 
-            // It it is edible, eat it!
-            let item_edible = provides_food.get(useitem.item);
-            match item_edible {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    let target = targets[0];
-                    let hc = hunger_clocks.get_mut(target);
-                    if let Some(hc) = hc {
-                        hc.state = HungerState::WellFed;
-                        hc.duration = 20;
-                        gamelog.entries.insert(
-                            0,
-                            format!("You eat the {}.", names.get(useitem.item).unwrap().name),
-                        );
-                    }
+                    //Targeting : le reste est résolu par la file d'effets
+                    let targets = match useitem.target {
+                        None => Targets::Single { target: entity },
+                        Some(target) => {
+                            let tile_idx = map.xy_idx(target.x, target.y) as i32;
+                            match aoe.get(useitem.item) {
+                                None => Targets::Tile { tile_idx },
+                                Some(area_effect) => Targets::Area {
+                                    tile_idx,
+                                    radius: area_effect.radius,
+                                },
+                            }
+                        }
+                    };
+                    effects.add_effect(
+                        Some(entity),
+                        EffectType::ItemUse { item: useitem.item },
+                        targets,
+                    );
                 }
-            }
-
-            //Si equippable, on l'équip, et deséquipe ce qui est présent actuellement
-            let item_equippable = equippable.get(useitem.item);
-            match item_equippable {
-                None => {}
                 Some(can_equip) => {
                     let target_slot = can_equip.slot;
-                    let target = targets[0];
+                    let target = entity;
+
+                    // Un objet maudit ne laisse pas sa place
+                    let stuck = (&equipped, &cursed, &names)
+                        .join()
+                        .find(|(worn, _, _)| worn.owner == target && worn.slot == target_slot)
+                        .map(|(_, _, name)| known_items.display_name(&name.name));
+                    if let Some(stuck) = stuck {
+                        if target == *player_entity {
+                            gamelog
+                                .entries
+                                .insert(0, format!("You can't take off {}, it is cursed.", stuck));
+                        }
+                        continue;
+                    }
+
+                    dirty
+                        .insert(target, EquipmentChanged {})
+                        .expect("Unable to insert");
 
                     //Enlève objets présent dans le slot d'item de la cible
                     let mut to_unequip: Vec<Entity> = Vec::new();
@@ -195,9 +204,13 @@ impl<'a> System<'a> for ItemUseSystem {
                         {
                             to_unequip.push(item_entity);
                             if target == *player_entity {
-                                gamelog
-                                    .entries
-                                    .insert(0, format!("You unequip {}.", name.name));
+                                gamelog.entries.insert(
+                                    0,
+                                    format!(
+                                        "You unequip {}.",
+                                        known_items.display_name(&name.name)
+                                    ),
+                                );
                             }
                         }
                     }
@@ -222,154 +235,24 @@ impl<'a> System<'a> for ItemUseSystem {
                     if target == *player_entity {
                         gamelog.entries.insert(
                             0,
-                            format!("You equip {}.", names.get(useitem.item).unwrap().name),
+                            format!(
+                                "You equip {}.",
+                                known_items.display_name(&names.get(useitem.item).unwrap().name)
+                            ),
                         );
-                    }
-                }
-            }
-
-            //Si dommage
-            let item_damages = inflict_damage.get(useitem.item);
-            match item_damages {
-                None => {}
-                Some(damage) => {
-                    used_item = false;
-                    for mob in targets.iter() {
-                        SufferDamage::new_damage(&mut suffer_damage, *mob, damage.damage, true);
-                        if entity == *player_entity {
-                            let mob_name = names.get(*mob).unwrap();
-                            let item_name = names.get(useitem.item).unwrap();
+                        if cursed.get(useitem.item).is_some() {
                             gamelog.entries.insert(
                                 0,
                                 format!(
-                                    "You use {} on {}, inflicting {} hp.",
-                                    item_name.name, mob_name.name, damage.damage
+                                    "{} is cursed, it won't come off!",
+                                    known_items
+                                        .display_name(&names.get(useitem.item).unwrap().name)
                                 ),
                             );
-                            let pos = positions.get(*mob);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::to_cp437('‼'),
-                                    rltk::RGB::named(rltk::RED),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    200.0,
-                                );
-                            }
-                        }
-
-                        used_item = true;
-                    }
-                }
-            }
-
-            //Confusion
-            let mut add_confusion = Vec::new();
-            {
-                let causes_confusion = confused.get(useitem.item);
-                match causes_confusion {
-                    None => {}
-                    Some(confusion) => {
-                        used_item = false;
-                        for mob in targets.iter() {
-                            add_confusion.push((*mob, confusion.turns));
-                            if entity == *player_entity {
-                                let mob_name = names.get(*mob).unwrap();
-                                let item_name = names.get(useitem.item).unwrap();
-                                gamelog.entries.insert(
-                                    0,
-                                    format!(
-                                        "You use {} on {}, confusing them.",
-                                        item_name.name, mob_name.name
-                                    ),
-                                );
-                                let pos = positions.get(*mob);
-                                if let Some(pos) = pos {
-                                    particle_builder.request(
-                                        pos.x,
-                                        pos.y,
-                                        rltk::to_cp437('?'),
-                                        rltk::RGB::named(rltk::MAGENTA),
-                                        rltk::RGB::named(rltk::BLACK),
-                                        200.0,
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            for mob in add_confusion.iter() {
-                confused
-                    .insert(mob.0, Confusion { turns: mob.1 })
-                    .expect("Unable to insert status");
-            }
-
-            //Si soin
-            let item_heals = healing.get(useitem.item);
-            match item_heals {
-                None => {}
-                Some(healer) => {
-                    used_item = false;
-                    for target in targets.iter() {
-                        let stats = combat_stats.get_mut(*target);
-                        if let Some(stats) = stats {
-                            let prev_hp = stats.hit_points.current;
-                            stats.hit_points.current = i32::min(
-                                stats.hit_points.max,
-                                stats.hit_points.current + healer.heal_amount,
-                            );
-                            if entity == *player_entity {
-                                gamelog.entries.insert(
-                                    0,
-                                    format!(
-                                        "You drink the {}, healing {} hp.",
-                                        names.get(useitem.item).unwrap().name,
-                                        (stats.hit_points.current - prev_hp).abs(),
-                                    ),
-                                );
-                            }
-                            used_item = true;
-
-                            let pos = positions.get(*target);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::to_cp437('♥'),
-                                    rltk::RGB::named(rltk::GREEN),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    200.0,
-                                );
-                            }
                         }
                     }
                 }
             }
-
-            //Magic Mapper
-            let is_mapper = magic_mapper.get(useitem.item);
-            match is_mapper {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    gamelog
-                        .entries
-                        .insert(0, "The map is revealed to you".to_string());
-                    *runstate = RunState::MagicMapReveal { row: 0 };
-                }
-            }
-
-            if used_item {
-                let consumable = consumables.get(useitem.item);
-                match consumable {
-                    None => {}
-                    Some(_) => {
-                        entities.delete(useitem.item).expect("Delete failed");
-                    }
-                }
-            }
         }
 
         wants_use.clear();
@@ -388,6 +271,8 @@ impl<'a> System<'a> for ItemDropSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, EquipmentChanged>,
+        ReadExpect<'a, KnownItems>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -399,6 +284,8 @@ impl<'a> System<'a> for ItemDropSystem {
             names,
             mut positions,
             mut backpack,
+            mut dirty,
+            known_items,
         ) = data;
 
         for (entity, to_drop) in (&entities, &wants_drop).join() {
@@ -418,11 +305,17 @@ impl<'a> System<'a> for ItemDropSystem {
                 )
                 .expect("Unable to insert position");
             backpack.remove(to_drop.item);
+            dirty
+                .insert(entity, EquipmentChanged {})
+                .expect("Unable to insert");
 
             if entity == *player_entity {
                 gamelog.entries.insert(
                     0,
-                    format!("You drop up the {}.", names.get(to_drop.item).unwrap().name),
+                    format!(
+                        "You drop up the {}.",
+                        known_items.display_name(&names.get(to_drop.item).unwrap().name)
+                    ),
                 );
             }
         }
@@ -443,6 +336,9 @@ impl<'a> System<'a> for ItemRemoveSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, EquipmentChanged>,
+        ReadStorage<'a, Cursed>,
+        ReadExpect<'a, KnownItems>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -454,18 +350,39 @@ impl<'a> System<'a> for ItemRemoveSystem {
             names,
             mut equipped,
             mut backpack,
+            mut dirty,
+            cursed,
+            known_items,
         ) = data;
 
         for (entity, to_remove) in (&entities, &wants_remove).join() {
+            if cursed.get(to_remove.item).is_some() {
+                if entity == *player_entity {
+                    gamelog.entries.insert(
+                        0,
+                        format!(
+                            "You can't remove {}, it is cursed.",
+                            known_items.display_name(&names.get(to_remove.item).unwrap().name)
+                        ),
+                    );
+                }
+                continue;
+            }
             equipped.remove(to_remove.item);
             backpack
                 .insert(to_remove.item, InBackpack { owner: entity })
                 .expect("Unable to insert backpack");
+            dirty
+                .insert(entity, EquipmentChanged {})
+                .expect("Unable to insert");
 
             if entity == *player_entity {
                 gamelog.entries.insert(
                     0,
-                    format!("You unequip {}.", names.get(to_remove.item).unwrap().name),
+                    format!(
+                        "You unequip {}.",
+                        known_items.display_name(&names.get(to_remove.item).unwrap().name)
+                    ),
                 );
             }
         }
@@ -473,3 +390,23 @@ impl<'a> System<'a> for ItemRemoveSystem {
         wants_remove.clear();
     }
 }
+
+/// Lifts the curses of everything `owner` wears. Returns the names of the freed items,
+/// as the player knows them.
+pub fn remove_curses(ecs: &World, owner: Entity) -> Vec<String> {
+    let entities = ecs.entities();
+    let equipped = ecs.read_storage::<Equipped>();
+    let names = ecs.read_storage::<Name>();
+    let known_items = ecs.fetch::<KnownItems>();
+    let mut cursed = ecs.write_storage::<Cursed>();
+
+    let freed: Vec<(Entity, String)> = (&entities, &equipped, &cursed, &names)
+        .join()
+        .filter(|(_, worn, _, _)| worn.owner == owner)
+        .map(|(item, _, _, name)| (item, known_items.display_name(&name.name)))
+        .collect();
+    for (item, _) in freed.iter() {
+        cursed.remove(*item);
+    }
+    freed.into_iter().map(|(_, name)| name).collect()
+}
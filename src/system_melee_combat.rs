@@ -1,10 +1,83 @@
 use super::{
-    gamelog::GameLog, skill_bonus, system_particle::ParticleBuilder, Attributes, EquipmentSlot,
-    Equipped, HungerClock, HungerState, MeleeWeapon, Name, NaturalAttackDefense, Pools, Position,
-    Skill, Skills, SufferDamage, WantsToMelee, WeaponAttribute, Wearable,
+    gamelog::GameLog, skill_bonus, Attributes, DamageType, EffectQueue, EffectType, EquipmentSlot,
+    Equipped, HungerClock, HungerState, MeleeWeapon, Name, NaturalAttackDefense, OnHit,
+    OnHitEffect, Pools, Skill, Skills, Targets, WantsToMelee, WeaponAttribute, Wearable,
 };
 use specs::prelude::*;
 
+/// Rolls the on-hit effects of an attack that just landed for `damage` hp.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_on_hit(
+    attacker: Entity,
+    target: Entity,
+    damage: i32,
+    on_hit: &[OnHit],
+    rng: &mut rltk::RandomNumberGenerator,
+    effects: &mut EffectQueue,
+    log: &mut GameLog,
+    names: &ReadStorage<Name>,
+) {
+    let attacker_name = names.get(attacker).map_or("Something", |n| n.name.as_str());
+    let target_name = names.get(target).map_or("something", |n| n.name.as_str());
+    for proc in on_hit.iter() {
+        if rng.roll_dice(1, 100) > proc.chance {
+            continue;
+        }
+        match proc.effect {
+            // add_status le dit déjà dans le log
+            OnHitEffect::Status {
+                kind,
+                turns,
+                potency,
+            } => effects.add_effect(
+                Some(attacker),
+                EffectType::Status {
+                    kind,
+                    turns,
+                    potency,
+                },
+                Targets::Single { target },
+            ),
+            OnHitEffect::Confusion { turns } => {
+                effects.add_effect(
+                    Some(attacker),
+                    EffectType::Confusion { turns },
+                    Targets::Single { target },
+                );
+                log.entries
+                    .insert(0, format!("{} is confused by the blow.", target_name));
+            }
+            OnHitEffect::Knockback { distance } => effects.add_effect(
+                Some(attacker),
+                EffectType::Knockback { distance },
+                Targets::Single { target },
+            ),
+            OnHitEffect::Disarm => effects.add_effect(
+                Some(attacker),
+                EffectType::Disarm,
+                Targets::Single { target },
+            ),
+            OnHitEffect::LifeDrain { percent } => {
+                let amount = damage * percent / 100;
+                if amount > 0 {
+                    effects.add_effect(
+                        None,
+                        EffectType::Healing { amount },
+                        Targets::Single { target: attacker },
+                    );
+                    log.entries.insert(
+                        0,
+                        format!(
+                            "{} drains {} hp from {}.",
+                            attacker_name, amount, target_name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
@@ -16,9 +89,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, Name>,
         ReadStorage<'a, Attributes>,
         ReadStorage<'a, Skills>,
-        WriteStorage<'a, SufferDamage>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
+        WriteExpect<'a, EffectQueue>,
         ReadStorage<'a, HungerClock>,
         ReadStorage<'a, Pools>,
         WriteExpect<'a, rltk::RandomNumberGenerator>,
@@ -26,7 +97,6 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, MeleeWeapon>,
         ReadStorage<'a, Wearable>,
         ReadStorage<'a, NaturalAttackDefense>,
-        ReadExpect<'a, Entity>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -37,9 +107,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
             names,
             attributes,
             skills,
-            mut inflict_damage,
-            mut particle_builder,
-            positions,
+            mut effects,
             hunger_clock,
             pools,
             mut rng,
@@ -47,7 +115,6 @@ impl<'a> System<'a> for MeleeCombatSystem {
             meleeweapons,
             wearables,
             natural,
-            player_entity,
         ) = data;
 
         for (entity, wants_melee, name, attacker_attributes, attacker_skills, attacker_pools) in (
@@ -73,19 +140,26 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     damage_n_dice: 1,
                     damage_die_type: 4,
                     damage_bonus: 0,
+                    damage_type: DamageType::Bludgeoning,
+                    on_hit: Vec::new(),
                 };
 
                 if let Some(nat) = natural.get(entity) {
-                    if !nat.attacks.is_empty() {
-                        let attack_index = if nat.attacks.len() == 1 {
+                    // Les attaques à distance ne servent pas au corps à corps
+                    let attacks: Vec<_> =
+                        nat.attacks.iter().filter(|a| a.range.is_none()).collect();
+                    if !attacks.is_empty() {
+                        let attack_index = if attacks.len() == 1 {
                             0
                         } else {
-                            rng.roll_dice(1, nat.attacks.len() as i32) as usize - 1
+                            rng.roll_dice(1, attacks.len() as i32) as usize - 1
                         };
-                        weapon_info.hit_bonus = nat.attacks[attack_index].hit_bonus;
-                        weapon_info.damage_n_dice = nat.attacks[attack_index].damage_n_dice;
-                        weapon_info.damage_die_type = nat.attacks[attack_index].damage_die_type;
-                        weapon_info.damage_bonus = nat.attacks[attack_index].damage_bonus;
+                        weapon_info.hit_bonus = attacks[attack_index].hit_bonus;
+                        weapon_info.damage_n_dice = attacks[attack_index].damage_n_dice;
+                        weapon_info.damage_die_type = attacks[attack_index].damage_die_type;
+                        weapon_info.damage_bonus = attacks[attack_index].damage_bonus;
+                        weapon_info.damage_type = attacks[attack_index].damage_type;
+                        weapon_info.on_hit = attacks[attack_index].on_hit.clone();
                     }
                 }
 
@@ -148,11 +222,15 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             + skill_damage_bonus
                             + weapon_damage_bonus,
                     );
-                    SufferDamage::new_damage(
-                        &mut inflict_damage,
-                        wants_melee.target,
-                        damage,
-                        entity == *player_entity,
+                    effects.add_effect(
+                        Some(entity),
+                        EffectType::Damage {
+                            amount: damage,
+                            damage_type: Some(weapon_info.damage_type),
+                        },
+                        Targets::Single {
+                            target: wants_melee.target,
+                        },
                     );
                     log.entries.insert(
                         0,
@@ -161,16 +239,28 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             &name.name, &target_name.name, damage
                         ),
                     );
-                    if let Some(pos) = positions.get(wants_melee.target) {
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            rltk::to_cp437('‼'),
-                            rltk::RGB::named(rltk::ORANGE),
-                            rltk::RGB::named(rltk::BLACK),
-                            200.0,
-                        );
-                    }
+                    apply_on_hit(
+                        entity,
+                        wants_melee.target,
+                        damage,
+                        &weapon_info.on_hit,
+                        &mut rng,
+                        &mut effects,
+                        &mut log,
+                        &names,
+                    );
+                    effects.add_effect(
+                        None,
+                        EffectType::Particle {
+                            glyph: rltk::to_cp437('‼'),
+                            fg: rltk::RGB::named(rltk::ORANGE),
+                            bg: rltk::RGB::named(rltk::BLACK),
+                            lifespan: 200.0,
+                        },
+                        Targets::Single {
+                            target: wants_melee.target,
+                        },
+                    );
                 } else if natural_roll == 1 {
                     // Natural 1 miss
                     log.entries.insert(
@@ -180,16 +270,18 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             name.name, target_name.name
                         ),
                     );
-                    if let Some(pos) = positions.get(wants_melee.target) {
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            rltk::to_cp437('‼'),
-                            rltk::RGB::named(rltk::BLUE),
-                            rltk::RGB::named(rltk::BLACK),
-                            200.0,
-                        );
-                    }
+                    effects.add_effect(
+                        None,
+                        EffectType::Particle {
+                            glyph: rltk::to_cp437('‼'),
+                            fg: rltk::RGB::named(rltk::BLUE),
+                            bg: rltk::RGB::named(rltk::BLACK),
+                            lifespan: 200.0,
+                        },
+                        Targets::Single {
+                            target: wants_melee.target,
+                        },
+                    );
                 } else {
                     // Miss
                     log.entries.insert(
@@ -199,16 +291,18 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             name.name, target_name.name
                         ),
                     );
-                    if let Some(pos) = positions.get(wants_melee.target) {
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            rltk::to_cp437('‼'),
-                            rltk::RGB::named(rltk::CYAN),
-                            rltk::RGB::named(rltk::BLACK),
-                            200.0,
-                        );
-                    }
+                    effects.add_effect(
+                        None,
+                        EffectType::Particle {
+                            glyph: rltk::to_cp437('‼'),
+                            fg: rltk::RGB::named(rltk::CYAN),
+                            bg: rltk::RGB::named(rltk::BLACK),
+                            lifespan: 200.0,
+                        },
+                        Targets::Single {
+                            target: wants_melee.target,
+                        },
+                    );
                 }
             }
         }
@@ -1,4 +1,4 @@
-use super::{ParticleLifetime, Position, Renderable, Rltk};
+use super::{ParticleLifetime, Position, Renderable};
 use rltk::RGB;
 use specs::prelude::*;
 
@@ -85,20 +85,38 @@ impl<'a> System<'a> for ParticleSpawnSystem {
     }
 }
 
-pub fn cull_dead_particles(ecs: &mut World, ctx: &Rltk) {
+/// Hides the particles whose lifetime is over. They are only deleted by
+/// `delete_particles`, so that frame timing never changes entity allocation
+/// (and a replay stays identical to the recorded game).
+pub fn cull_dead_particles(ecs: &mut World, frame_time_ms: f32) {
     let mut dead_particles: Vec<Entity> = Vec::new();
     {
         //Age of particles
         let mut particles = ecs.write_storage::<ParticleLifetime>();
         let entities = ecs.entities();
         for (entity, mut particle) in (&entities, &mut particles).join() {
-            particle.lifetime_ms -= ctx.frame_time_ms;
+            particle.lifetime_ms -= frame_time_ms;
             if particle.lifetime_ms < 0.0 {
                 dead_particles.push(entity);
             }
         }
     }
+    let mut positions = ecs.write_storage::<Position>();
+    let mut renderables = ecs.write_storage::<Renderable>();
     for dead in dead_particles.iter() {
-        ecs.delete_entity(*dead).expect("Particle will not die");
+        positions.remove(*dead);
+        renderables.remove(*dead);
+    }
+}
+
+/// Deletes every particle, visible or not. Called once per player command.
+pub fn delete_particles(ecs: &mut World) {
+    let particles: Vec<Entity> = {
+        let lifetimes = ecs.read_storage::<ParticleLifetime>();
+        let entities = ecs.entities();
+        (&entities, &lifetimes).join().map(|p| p.0).collect()
+    };
+    for particle in particles.iter() {
+        ecs.delete_entity(*particle).expect("Particle will not die");
     }
 }
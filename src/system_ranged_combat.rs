@@ -0,0 +1,284 @@
+use super::{
+    gamelog::GameLog, ActionKind, Attributes, EffectQueue, EffectType, Energy, Equipped,
+    HungerClock, HungerState, Map, MeleeWeapon, Name, NaturalAttackDefense, Pools, Position,
+    RangedWeapon, Skill, Skills, Targets, WantsToShoot, WeaponAttribute, Wearable,
+};
+use rltk::{BaseMap, DistanceAlg, LineAlg, Point};
+use specs::prelude::*;
+
+/// A shot: the weapon (or natural attack) used, and how far it reaches.
+pub struct RangedAttack {
+    pub weapon: MeleeWeapon,
+    pub range: i32,
+}
+
+/// The ranged attack of `entity`: its wielded ranged weapon, or else one of its
+/// ranged natural attacks. `None` if it can't shoot.
+pub fn ranged_attack(
+    entity: Entity,
+    rng: Option<&mut rltk::RandomNumberGenerator>,
+    natural: &ReadStorage<NaturalAttackDefense>,
+    equipped: &ReadStorage<Equipped>,
+    melee_weapons: &ReadStorage<MeleeWeapon>,
+    ranged_weapons: &ReadStorage<RangedWeapon>,
+) -> Option<RangedAttack> {
+    for (wielded, weapon, ranged) in (equipped, melee_weapons, ranged_weapons).join() {
+        if wielded.owner == entity {
+            return Some(RangedAttack {
+                weapon: weapon.clone(),
+                range: ranged.range,
+            });
+        }
+    }
+
+    let attacks: Vec<_> = natural
+        .get(entity)?
+        .attacks
+        .iter()
+        .filter(|a| a.range.is_some())
+        .collect();
+    if attacks.is_empty() {
+        return None;
+    }
+    let attack_index = match rng {
+        Some(rng) if attacks.len() > 1 => rng.roll_dice(1, attacks.len() as i32) as usize - 1,
+        _ => 0,
+    };
+    let attack = attacks[attack_index];
+    Some(RangedAttack {
+        weapon: MeleeWeapon {
+            attribute: WeaponAttribute::Quickness,
+            hit_bonus: attack.hit_bonus,
+            damage_n_dice: attack.damage_n_dice,
+            damage_die_type: attack.damage_die_type,
+            damage_bonus: attack.damage_bonus,
+            damage_type: attack.damage_type,
+            on_hit: attack.on_hit.clone(),
+        },
+        range: attack.range.unwrap_or(0),
+    })
+}
+
+/// Nothing opaque between the two tiles (the tiles themselves excluded).
+pub fn line_of_fire(map: &Map, from: Point, to: Point) -> bool {
+    rltk::line2d(LineAlg::Bresenham, from, to)
+        .iter()
+        .filter(|p| **p != from && **p != to)
+        .all(|p| !map.is_opaque(map.xy_idx(p.x, p.y)))
+}
+
+/// Resolves `WantsToShoot` : like melee, but the hit roll uses Quickness and loses
+/// one point every two tiles of distance.
+pub struct RangedCombatSystem {}
+
+impl<'a> System<'a> for RangedCombatSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        WriteExpect<'a, EffectQueue>,
+        ReadStorage<'a, HungerClock>,
+        ReadStorage<'a, Pools>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, MeleeWeapon>,
+        ReadStorage<'a, RangedWeapon>,
+        ReadStorage<'a, Wearable>,
+        ReadStorage<'a, NaturalAttackDefense>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, Energy>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut log,
+            mut wants_shoot,
+            names,
+            attributes,
+            skills,
+            mut effects,
+            hunger_clock,
+            pools,
+            mut rng,
+            equipped_items,
+            melee_weapons,
+            ranged_weapons,
+            wearables,
+            natural,
+            positions,
+            map,
+            mut energies,
+        ) = data;
+
+        for (entity, wants_shoot, name, attacker_attributes, attacker_pools, pos) in (
+            &entities,
+            &wants_shoot,
+            &names,
+            &attributes,
+            &pools,
+            &positions,
+        )
+            .join()
+        {
+            if let Some(energy) = energies.get_mut(entity) {
+                energy.spend(ActionKind::Shoot);
+            }
+            let target = wants_shoot.target;
+            let (target_pools, target_attributes, target_skills, target_name, target_pos) = match (
+                pools.get(target),
+                attributes.get(target),
+                skills.get(target),
+                names.get(target),
+                positions.get(target),
+            ) {
+                (Some(p), Some(a), Some(s), Some(n), Some(t)) => (p, a, s, n, t),
+                _ => continue,
+            };
+            if attacker_pools.hit_points.current <= 0 || target_pools.hit_points.current <= 0 {
+                continue;
+            }
+
+            let attack = match ranged_attack(
+                entity,
+                Some(&mut *rng),
+                &natural,
+                &equipped_items,
+                &melee_weapons,
+                &ranged_weapons,
+            ) {
+                None => continue,
+                Some(attack) => attack,
+            };
+
+            let from = Point::new(pos.x, pos.y);
+            let to = Point::new(target_pos.x, target_pos.y);
+            let distance = DistanceAlg::Pythagoras.distance2d(from, to);
+            if distance > attack.range as f32 || !line_of_fire(&map, from, to) {
+                log.entries.insert(
+                    0,
+                    format!(
+                        "{} can't get a clear shot at {}.",
+                        name.name, target_name.name
+                    ),
+                );
+                continue;
+            }
+
+            // La trajectoire du projectile
+            for p in rltk::line2d(LineAlg::Bresenham, from, to)
+                .iter()
+                .filter(|p| **p != from && **p != to)
+            {
+                effects.add_effect(
+                    None,
+                    EffectType::Particle {
+                        glyph: rltk::to_cp437('∙'),
+                        fg: rltk::RGB::named(rltk::YELLOW),
+                        bg: rltk::RGB::named(rltk::BLACK),
+                        lifespan: 100.0,
+                    },
+                    Targets::Tile {
+                        tile_idx: map.xy_idx(p.x, p.y) as i32,
+                    },
+                );
+            }
+
+            let natural_roll = rng.roll_dice(1, 20);
+            let range_penalty = distance as i32 / 2;
+            let mut status_hit_bonus = 0;
+            if let Some(hc) = hunger_clock.get(entity) {
+                // Well-Fed grants +1
+                if hc.state == HungerState::WellFed {
+                    status_hit_bonus += 1;
+                }
+            }
+            let modified_hit_roll = natural_roll
+                + attacker_attributes.quickness.bonus
+                + attack.weapon.hit_bonus
+                + status_hit_bonus
+                - range_penalty;
+
+            let mut armor_item_bonus_f = 0.0;
+            for (wielded, armor) in (&equipped_items, &wearables).join() {
+                if wielded.owner == target {
+                    armor_item_bonus_f += armor.armor_class;
+                }
+            }
+            let base_armor_class = match natural.get(target) {
+                None => 10,
+                Some(nat) => nat.armor_class.unwrap_or(10),
+            };
+            let armor_class = base_armor_class
+                + target_attributes.quickness.bonus
+                + super::skill_bonus(Skill::Defense, target_skills)
+                + armor_item_bonus_f as i32;
+
+            if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class) {
+                let base_damage =
+                    rng.roll_dice(attack.weapon.damage_n_dice, attack.weapon.damage_die_type);
+                let damage = i32::max(
+                    0,
+                    base_damage + attacker_attributes.quickness.bonus + attack.weapon.damage_bonus,
+                );
+                effects.add_effect(
+                    Some(entity),
+                    EffectType::Damage {
+                        amount: damage,
+                        damage_type: Some(attack.weapon.damage_type),
+                    },
+                    Targets::Single { target },
+                );
+                log.entries.insert(
+                    0,
+                    format!(
+                        "{} shoots {}, for {} hp.",
+                        &name.name, &target_name.name, damage
+                    ),
+                );
+                super::apply_on_hit(
+                    entity,
+                    target,
+                    damage,
+                    &attack.weapon.on_hit,
+                    &mut rng,
+                    &mut effects,
+                    &mut log,
+                    &names,
+                );
+                effects.add_effect(
+                    None,
+                    EffectType::Particle {
+                        glyph: rltk::to_cp437('‼'),
+                        fg: rltk::RGB::named(rltk::ORANGE),
+                        bg: rltk::RGB::named(rltk::BLACK),
+                        lifespan: 200.0,
+                    },
+                    Targets::Single { target },
+                );
+            } else {
+                log.entries.insert(
+                    0,
+                    format!("{} shoots at {}, but misses.", name.name, target_name.name),
+                );
+                effects.add_effect(
+                    None,
+                    EffectType::Particle {
+                        glyph: rltk::to_cp437('‼'),
+                        fg: rltk::RGB::named(rltk::CYAN),
+                        bg: rltk::RGB::named(rltk::BLACK),
+                        lifespan: 200.0,
+                    },
+                    Targets::Single { target },
+                );
+            }
+        }
+
+        wants_shoot.clear();
+    }
+}
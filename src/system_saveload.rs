@@ -56,6 +56,8 @@ pub fn save_game(ecs: &mut World) {
         .get_mut::<super::map::MasterDungeonMap>()
         .unwrap()
         .clone();
+    let known_items = ecs.get_mut::<super::KnownItems>().unwrap().clone();
+    let replay = ecs.get_mut::<super::replay::Replay>().unwrap().clone();
     let savehelper = ecs
         .create_entity()
         .with(SerializationHelper { map: mapcopy })
@@ -65,6 +67,8 @@ pub fn save_game(ecs: &mut World) {
         .create_entity()
         .with(DMSerializationHelper {
             map: dungeon_master,
+            known_items,
+            replay: Some(replay),
         })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
@@ -93,11 +97,16 @@ pub fn save_game(ecs: &mut World) {
             Viewshed,
             Name,
             BlocksTile,
-            SufferDamage,
             Ranged,
             InflictsDamage,
             AreaOfEffect,
             Confusion,
+            StatusEffects,
+            InflictsStatus,
+            SpellTemplate,
+            KnownSpells,
+            TeachesSpell,
+            Resistances,
             Equippable,
             MeleeWeapon,
             Wearable,
@@ -113,22 +122,36 @@ pub fn save_game(ecs: &mut World) {
             Door,
             Attributes,
             Skills,
+            LevelUpPoints,
             Pools,
             NaturalAttackDefense,
             LootTable,
             OtherLevelPosition,
             LightSource,
-            Initiative,
+            Energy,
+            EquipmentChanged,
+            Rarity,
+            Cursed,
+            RemovesCurse,
+            StatusChanged,
+            AttributeModifiers,
+            SkillModifiers,
             MyTurn,
             Faction,
             WantsToApproach,
             WantsToFlee,
             MoveMode,
             Chasing,
+            ChaseSettings,
             //With entity
             InBackpack,
             WantsToMelee,
             WantsToMelee,
+            WantsToShoot,
+            WantsToCastSpell,
+            RangedWeapon,
+            Ammunition,
+            Quantity,
             WantsToUseItem,
             WantsToDropItem,
             Equipped,
@@ -181,11 +204,16 @@ pub fn load_game(ecs: &mut World) {
             Viewshed,
             Name,
             BlocksTile,
-            SufferDamage,
             Ranged,
             InflictsDamage,
             AreaOfEffect,
             Confusion,
+            StatusEffects,
+            InflictsStatus,
+            SpellTemplate,
+            KnownSpells,
+            TeachesSpell,
+            Resistances,
             Equippable,
             MeleeWeapon,
             Wearable,
@@ -201,22 +229,36 @@ pub fn load_game(ecs: &mut World) {
             Door,
             Attributes,
             Skills,
+            LevelUpPoints,
             Pools,
             NaturalAttackDefense,
             LootTable,
             OtherLevelPosition,
             LightSource,
-            Initiative,
+            Energy,
+            EquipmentChanged,
+            Rarity,
+            Cursed,
+            RemovesCurse,
+            StatusChanged,
+            AttributeModifiers,
+            SkillModifiers,
             MyTurn,
             Faction,
             WantsToApproach,
             WantsToFlee,
             MoveMode,
             Chasing,
+            ChaseSettings,
             //with entity
             InBackpack,
             WantsToMelee,
             WantsToMelee,
+            WantsToShoot,
+            WantsToCastSpell,
+            RangedWeapon,
+            Ammunition,
+            Quantity,
             WantsToUseItem,
             WantsToDropItem,
             Equipped,
@@ -243,6 +285,16 @@ pub fn load_game(ecs: &mut World) {
         for (e, h) in (&entities, &helper2).join() {
             let mut dungeonmaster = ecs.write_resource::<super::map::MasterDungeonMap>();
             *dungeonmaster = h.map.clone();
+            *ecs.write_resource::<super::KnownItems>() = h.known_items.clone();
+            // Le RNG est réensemencé au prochain tour, d'après le nombre de tours joués
+            let mut replay = ecs.write_resource::<super::replay::Replay>();
+            match &h.replay {
+                Some(saved) => *replay = saved.clone(),
+                None => {
+                    *replay = super::replay::Replay::new(h.map.seed);
+                    replay.stop_recording();
+                }
+            }
             deleteme2 = Some(e);
         }
         for (e, _p, pos) in (&entities, &player, &position).join() {
@@ -0,0 +1,161 @@
+use super::{
+    gamelog::GameLog, skill_bonus, AreaOfEffect, Attributes, EffectQueue, EffectType, KnownSpells,
+    Map, MyTurn, Name, Pools, Ranged, Skill, Skills, SpellTemplate, Targets, WantsToCastSpell,
+};
+use specs::prelude::*;
+
+/// The first spell of `known` that reaches `distance` without catching its caster
+/// in the blast, and that `mana` and a Magic skill of `magic` are enough for.
+/// Spells cast on oneself are left out.
+#[allow(clippy::too_many_arguments)]
+pub fn spell_in_range(
+    known: &KnownSpells,
+    mana: i32,
+    magic: i32,
+    distance: f32,
+    entities: &Entities,
+    templates: &ReadStorage<SpellTemplate>,
+    names: &ReadStorage<Name>,
+    ranged: &ReadStorage<Ranged>,
+    aoe: &ReadStorage<AreaOfEffect>,
+) -> Option<Entity> {
+    known.spells.iter().find_map(|spell_name| {
+        let (spell, template, _name) = (entities, templates, names)
+            .join()
+            .find(|(_, _, name)| name.name == *spell_name)?;
+        let range = ranged.get(spell)?.range;
+        let radius = aoe.get(spell).map_or(0, |area| area.radius);
+        if template.mana_cost <= mana
+            && template.magic_skill <= magic
+            && distance <= range as f32
+            && distance > radius as f32
+        {
+            Some(spell)
+        } else {
+            None
+        }
+    })
+}
+
+/// Checks the Magic skill of the caster and pays the mana of `WantsToCastSpell`, then lets the effects queue apply the spell
+/// like a consumable that isn't used up.
+pub struct SpellUseSystem {}
+
+impl<'a> System<'a> for SpellUseSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, WantsToCastSpell>,
+        ReadStorage<'a, SpellTemplate>,
+        ReadStorage<'a, AreaOfEffect>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Pools>,
+        ReadStorage<'a, Skills>,
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, EffectQueue>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut wants_cast,
+            templates,
+            aoe,
+            names,
+            mut pools,
+            skills,
+            map,
+            player_entity,
+            mut effects,
+            mut log,
+        ) = data;
+
+        for (entity, cast, caster_pools) in (&entities, &wants_cast, &mut pools).join() {
+            let template = match templates.get(cast.spell) {
+                None => continue,
+                Some(template) => template,
+            };
+            let spell_name = names.get(cast.spell).unwrap().name.clone();
+            let magic = skills
+                .get(entity)
+                .map_or(0, |s| skill_bonus(Skill::Magic, s));
+            if magic < template.magic_skill {
+                if entity == *player_entity {
+                    log.entries.insert(
+                        0,
+                        format!(
+                            "You can't cast {}, it needs a Magic skill of {}.",
+                            spell_name, template.magic_skill
+                        ),
+                    );
+                }
+                continue;
+            }
+            if caster_pools.mana.current < template.mana_cost {
+                if entity == *player_entity {
+                    log.entries.insert(
+                        0,
+                        format!("You don't have enough mana to cast {}.", spell_name),
+                    );
+                }
+                continue;
+            }
+            caster_pools.mana.current -= template.mana_cost;
+
+            if entity == *player_entity {
+                log.entries.insert(0, format!("You cast {}.", spell_name));
+            } else if let Some(name) = names.get(entity) {
+                log.entries
+                    .insert(0, format!("{} casts {}.", name.name, spell_name));
+            }
+
+            let targets = match cast.target {
+                None => Targets::Single { target: entity },
+                Some(target) => {
+                    let tile_idx = map.xy_idx(target.x, target.y) as i32;
+                    match aoe.get(cast.spell) {
+                        None => Targets::Tile { tile_idx },
+                        Some(area_effect) => Targets::Area {
+                            tile_idx,
+                            radius: area_effect.radius,
+                        },
+                    }
+                }
+            };
+            effects.add_effect(
+                Some(entity),
+                EffectType::SpellUse { spell: cast.spell },
+                targets,
+            );
+        }
+
+        wants_cast.clear();
+    }
+}
+
+/// One chance in six per turn to get a mana point back, plus one per point of
+/// Intelligence bonus.
+pub struct ManaRegenSystem {}
+
+impl<'a> System<'a> for ManaRegenSystem {
+    type SystemData = (
+        ReadStorage<'a, MyTurn>,
+        ReadStorage<'a, Attributes>,
+        WriteStorage<'a, Pools>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (turns, attributes, mut pools, mut rng) = data;
+
+        for (_turn, attr, stats) in (&turns, &attributes, &mut pools).join() {
+            if stats.mana.current < stats.mana.max
+                && rng.roll_dice(1, 6) <= 1 + attr.intelligence.bonus
+            {
+                stats.mana.current += 1;
+            }
+        }
+    }
+}
@@ -1,7 +1,4 @@
-use super::{
-    gamelog::GameLog, EntityMoved, EntryTrigger, Hidden, InflictsDamage, Map, Name,
-    ParticleBuilder, Position, SingleActivation, SufferDamage,
-};
+use super::{EffectQueue, EffectType, EntityMoved, EntryTrigger, Map, Position, Targets};
 use specs::prelude::*;
 
 pub struct TriggerSystem {}
@@ -13,84 +10,30 @@ impl<'a> System<'a> for TriggerSystem {
         WriteStorage<'a, EntityMoved>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, EntryTrigger>,
-        WriteStorage<'a, Hidden>,
-        ReadStorage<'a, Name>,
         Entities<'a>,
-        WriteExpect<'a, GameLog>,
-        ReadStorage<'a, InflictsDamage>,
-        WriteExpect<'a, ParticleBuilder>,
-        WriteStorage<'a, SufferDamage>,
-        ReadStorage<'a, SingleActivation>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (
-            map,
-            mut entity_moved,
-            position,
-            entry_trigger,
-            mut hidden,
-            names,
-            entities,
-            mut log,
-            inflicts_damage, //InflictsDamage
-            mut particle_builder,
-            mut inflict_damage, //SufferDamage
-            single_activation,
-        ) = data;
+        let (map, mut entity_moved, position, entry_trigger, entities, mut effects) = data;
 
         // Iterate the entities that moved and their final position
-        let mut remove_entities: Vec<Entity> = Vec::new();
         for (entity, mut _entity_moved, pos) in (&entities, &mut entity_moved, &position).join() {
             let idx = map.xy_idx(pos.x, pos.y);
             for entity_id in map.tile_content[idx].iter() {
-                if entity != *entity_id {
-                    // Do not bother to check yourself for being a trap!
-                    let maybe_trigger = entry_trigger.get(*entity_id);
-                    match maybe_trigger {
-                        None => {}
-                        Some(_trigger) => {
-                            // We triggered it
-                            let name = names.get(*entity_id);
-                            if let Some(name) = name {
-                                log.entries.insert(0, format!("{} triggers!", &name.name));
-                            }
-
-                            hidden.remove(*entity_id); // The trap is no longer hidden
-
-                            // If the trap is damage inflicting, do it
-                            let damage = inflicts_damage.get(*entity_id);
-                            if let Some(damage) = damage {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::to_cp437('‼'),
-                                    rltk::RGB::named(rltk::ORANGE),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    200.0,
-                                );
-                                SufferDamage::new_damage(
-                                    &mut inflict_damage,
-                                    entity,
-                                    damage.damage,
-                                    false,
-                                );
-                            }
-
-                            // If it is single activation, it needs to be removed
-                            let sa = single_activation.get(*entity_id);
-                            if let Some(_sa) = sa {
-                                remove_entities.push(*entity_id);
-                            }
-                        }
-                    }
+                // Do not bother to check yourself for being a trap!
+                if entity != *entity_id && entry_trigger.get(*entity_id).is_some() {
+                    // We triggered it
+                    effects.add_effect(
+                        Some(*entity_id),
+                        EffectType::TriggerFire {
+                            trigger: *entity_id,
+                        },
+                        Targets::Single { target: entity },
+                    );
                 }
             }
         }
-        // Remove any single activation traps
-        for trap in remove_entities.iter() {
-            entities.delete(*trap).expect("Unable to delete trap");
-        }
 
         // Remove all entity movement markers
         entity_moved.clear();
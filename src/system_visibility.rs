@@ -1,4 +1,7 @@
-use super::{BlocksVisibility, GameLog, Hidden, Map, Name, Player, Position, Viewshed};
+use super::{
+    BlocksVisibility, GameLog, Hidden, Map, Name, Player, Position, StatusEffects, StatusKind,
+    Viewshed,
+};
 use rltk::{field_of_view, Point};
 use specs::prelude::*;
 
@@ -17,6 +20,7 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, GameLog>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, BlocksVisibility>,
+        ReadStorage<'a, StatusEffects>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -31,6 +35,7 @@ impl<'a> System<'a> for VisibilitySystem {
             mut log,
             names,
             blocks_visibility,
+            statuses,
         ) = data;
 
         map.view_blocked.clear();
@@ -43,12 +48,16 @@ impl<'a> System<'a> for VisibilitySystem {
             if viewshed.dirty {
                 viewshed.dirty = false;
                 viewshed.visible_tiles.clear();
-                viewshed.visible_tiles =
-                    field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+                // Aveuglé, on ne voit que les cases voisines
+                let blind = statuses.get(ent).is_some_and(|s| s.has(StatusKind::Blind));
+                let range = if blind { 1 } else { viewshed.range };
+                viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), range, &*map);
                 //Ne retiens que les tiles visibles, rejetant celles hors champ.
                 viewshed
                     .visible_tiles
                     .retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
+                //field_of_view vient d'un HashSet : l'ordre doit être stable pour les parties rejouées
+                viewshed.visible_tiles.sort_by_key(|p| (p.y, p.x));
 
                 //Revèle ce que le joueur voit si l'entité sèl. est le joueur
                 let p: Option<&Player> = player.get(ent);